@@ -3,48 +3,359 @@ use std::fs::File;
 use std::io::{BufWriter, Write};
 use std::path::Path;
 
+#[path = "src/normalize.rs"]
+mod normalize;
+
 fn main() {
     let path = Path::new(&env::var("OUT_DIR").unwrap()).join("codegen.rs");
     let mut file = BufWriter::new(File::create(&path).unwrap());
 
-    // We do the mapping in this direction because EVE has items with
-    // dupe names but different codes. The iteration order over the CSV
-    // should ensure that the highest ID associated with a given name
-    // will be used.
-    let mut mapping_name_to_id: std::collections::HashMap<String, String> =
-        std::collections::HashMap::new();
+    // Without the compiled-data feature the type data is loaded at runtime,
+    // so there's nothing to generate and data/ doesn't need to exist.
+    if env::var("CARGO_FEATURE_COMPILED_DATA").is_err() {
+        return;
+    }
 
     println!("cargo::rerun-if-changed=data/invTypes.csv");
+    println!("cargo::rerun-if-changed=src/normalize.rs");
     let mut sde_reader = csv::ReaderBuilder::new()
         .from_path("data/invTypes.csv")
         .unwrap();
 
+    // The rest of the invTypes columns for TypeInfo, as a CompiledTypeInfo
+    // tuple so that it can live in a static.
+    let mut builder_code_to_info = phf_codegen::Map::new();
+
+    // We map names to all of their IDs because EVE has items with dupe
+    // names but different codes. Published and market group let the
+    // ResolutionPolicy pick between them at lookup time.
+    let mut mapping_name_to_ids: std::collections::HashMap<String, Vec<u64>> =
+        std::collections::HashMap::new();
+    let mut builder_code_to_item = phf_codegen::Map::new();
+
     for result in sde_reader.records() {
         let record = result.unwrap();
-        mapping_name_to_id.insert(record[2].to_string(), record[0].to_string());
+        let id_parsed: u64 = record[0].parse().unwrap();
+        let group: u64 = record[1].parse().unwrap();
+        let mass: f64 = record[4].parse().unwrap_or(0.0);
+        let volume: f64 = record[5].parse().unwrap_or(0.0);
+        let capacity: f64 = record[6].parse().unwrap_or(0.0);
+        let portion_size: u64 = record[7].parse().unwrap_or(1);
+        let base_price: Option<f64> = record[9].parse().ok();
+        let published = &record[10] == "1" || record[10].eq_ignore_ascii_case("true");
+        let market_group: Option<u64> = record[11].parse().ok();
+        builder_code_to_info.entry(
+            id_parsed,
+            &format!(
+                "({}, {:?}, {:?}, {:?}, {}, {:?}, {}, {:?})",
+                group, volume, capacity, mass, portion_size, base_price, published, market_group
+            ),
+        );
+
+        mapping_name_to_ids
+            .entry(record[2].to_string())
+            .or_default()
+            .push(id_parsed);
+        builder_code_to_item.entry(id_parsed, &format!("r#\"{}\"#", &record[2]));
     }
 
-    let mut builder_code_to_item = phf_codegen::Map::new();
-    let mut builder_item_to_code = phf_codegen::Map::new();
-    for (name, id) in mapping_name_to_id.iter() {
-        let id_parsed: u64 = id.parse().unwrap();
-        builder_code_to_item.entry(id_parsed, &format!("r#\"{}\"#", name));
-        builder_item_to_code.entry(name, &format!("{}", id_parsed));
+    for ids in mapping_name_to_ids.values_mut() {
+        ids.sort();
+    }
+
+    // Several names can normalise to the same key, e.g. ones that only
+    // differ in case. Go through them in sorted order so that the same one
+    // wins on every build.
+    let mut normalized_to_name: std::collections::BTreeMap<String, &String> =
+        std::collections::BTreeMap::new();
+    let mut names: Vec<&String> = mapping_name_to_ids.keys().collect();
+    names.sort();
+    for name in names {
+        normalized_to_name
+            .entry(normalize::normalize_name(name))
+            .or_insert(name);
+    }
+    let mut builder_normalized_to_item = phf_codegen::Map::new();
+    for (normalized, name) in normalized_to_name.iter() {
+        builder_normalized_to_item.entry(normalized.as_str(), &format!("r#\"{}\"#", name));
+    }
+
+    let mut builder_item_to_codes = phf_codegen::Map::new();
+    for (name, ids) in mapping_name_to_ids.iter() {
+        let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        builder_item_to_codes.entry(name, &format!("&[{}]", ids.join(", ")));
     }
 
     write!(
         &mut file,
-        "static ITEM_TO_CODE: phf::Map<&'static str, u64> = {}",
-        builder_item_to_code.build(),
+        "static CODE_TO_ITEM: phf::Map<u64, &'static str> = {}",
+        builder_code_to_item.build(),
     )
     .unwrap();
-    write!(&mut file, ";\n").unwrap();
+    writeln!(&mut file, ";").unwrap();
 
     write!(
         &mut file,
-        "static CODE_TO_ITEM: phf::Map<u64, &'static str> = {}",
-        builder_code_to_item.build(),
+        "static ITEM_TO_CODES: phf::Map<&'static str, &'static [u64]> = {}",
+        builder_item_to_codes.build(),
+    )
+    .unwrap();
+    writeln!(&mut file, ";").unwrap();
+
+    write!(
+        &mut file,
+        "static CODE_TO_INFO: phf::Map<u64, CompiledTypeInfo> = {}",
+        builder_code_to_info.build(),
     )
     .unwrap();
-    write!(&mut file, ";\n").unwrap();
+    writeln!(&mut file, ";").unwrap();
+
+    write!(
+        &mut file,
+        "static NORMALIZED_TO_ITEM: phf::Map<&'static str, &'static str> = {}",
+        builder_normalized_to_item.build(),
+    )
+    .unwrap();
+    writeln!(&mut file, ";").unwrap();
+
+    // The rest of the tables are optional, without them the compiled data
+    // just doesn't know about e.g. groups and categories.
+    let mut groups = Vec::new();
+    if let Some(mut reader) = optional_table("data/invGroups.csv") {
+        for result in reader.records() {
+            let record = result.unwrap();
+            let published = &record[8] == "1" || record[8].eq_ignore_ascii_case("true");
+            groups.push(format!(
+                "({}, {}, r#\"{}\"#, {})",
+                &record[0], &record[1], &record[2], published
+            ));
+        }
+    }
+    writeln!(
+        &mut file,
+        "static GROUPS: &[(u64, u64, &str, bool)] = &[{}];",
+        groups.join(", ")
+    )
+    .unwrap();
+
+    let mut categories = Vec::new();
+    if let Some(mut reader) = optional_table("data/invCategories.csv") {
+        for result in reader.records() {
+            let record = result.unwrap();
+            let published = &record[3] == "1" || record[3].eq_ignore_ascii_case("true");
+            categories.push(format!(
+                "({}, r#\"{}\"#, {})",
+                &record[0], &record[1], published
+            ));
+        }
+    }
+    writeln!(
+        &mut file,
+        "static CATEGORIES: &[(u64, &str, bool)] = &[{}];",
+        categories.join(", ")
+    )
+    .unwrap();
+
+    let mut market_groups = Vec::new();
+    if let Some(mut reader) = optional_table("data/invMarketGroups.csv") {
+        for result in reader.records() {
+            let record = result.unwrap();
+            let parent: Option<u64> = record[1].parse().ok();
+            let has_types = &record[5] == "1" || record[5].eq_ignore_ascii_case("true");
+            market_groups.push(format!(
+                "({}, {:?}, r#\"{}\"#, {})",
+                &record[0], parent, &record[2], has_types
+            ));
+        }
+    }
+    writeln!(
+        &mut file,
+        "static MARKET_GROUPS: &[(u64, Option<u64>, &str, bool)] = &[{}];",
+        market_groups.join(", ")
+    )
+    .unwrap();
+
+    let mut packaged_volumes = Vec::new();
+    if let Some(mut reader) = optional_table("data/invVolumes.csv") {
+        for result in reader.records() {
+            let record = result.unwrap();
+            let volume: f64 = record[1].parse().unwrap();
+            packaged_volumes.push(format!("({}, {:?})", &record[0], volume));
+        }
+    }
+    writeln!(
+        &mut file,
+        "static PACKAGED_VOLUMES: &[(u64, f64)] = &[{}];",
+        packaged_volumes.join(", ")
+    )
+    .unwrap();
+
+    let mut type_materials = Vec::new();
+    if let Some(mut reader) = optional_table("data/invTypeMaterials.csv") {
+        for result in reader.records() {
+            let record = result.unwrap();
+            type_materials.push(format!("({}, {}, {})", &record[0], &record[1], &record[2]));
+        }
+    }
+    writeln!(
+        &mut file,
+        "static TYPE_MATERIALS: &[(u64, u64, u64)] = &[{}];",
+        type_materials.join(", ")
+    )
+    .unwrap();
+
+    // (blueprint, activity, type, quantity) rows, which TypeDatabase turns
+    // into blueprints
+    for (name, path) in [
+        ("BLUEPRINT_PRODUCTS", "data/industryActivityProducts.csv"),
+        ("BLUEPRINT_MATERIALS", "data/industryActivityMaterials.csv"),
+    ] {
+        let mut rows = Vec::new();
+        if let Some(mut reader) = optional_table(path) {
+            for result in reader.records() {
+                let record = result.unwrap();
+                rows.push(format!(
+                    "({}, {}, {}, {})",
+                    &record[0], &record[1], &record[2], &record[3]
+                ));
+            }
+        }
+        writeln!(
+            &mut file,
+            "static {}: &[(u64, u64, u64, u64)] = &[{}];",
+            name,
+            rows.join(", ")
+        )
+        .unwrap();
+    }
+
+    let mut meta_groups = Vec::new();
+    if let Some(mut reader) = optional_table("data/invMetaGroups.csv") {
+        for result in reader.records() {
+            let record = result.unwrap();
+            meta_groups.push(format!("({}, r#\"{}\"#)", &record[0], &record[1]));
+        }
+    }
+    writeln!(
+        &mut file,
+        "static META_GROUPS: &[(u64, &str)] = &[{}];",
+        meta_groups.join(", ")
+    )
+    .unwrap();
+
+    let mut meta_types = Vec::new();
+    if let Some(mut reader) = optional_table("data/invMetaTypes.csv") {
+        for result in reader.records() {
+            let record = result.unwrap();
+            let parent: Option<u64> = record[1].parse().ok();
+            let meta_group: Option<u64> = record[2].parse().ok();
+            meta_types.push(format!("({}, {:?}, {:?})", &record[0], parent, meta_group));
+        }
+    }
+    writeln!(
+        &mut file,
+        "static META_TYPES: &[(u64, Option<u64>, Option<u64>)] = &[{}];",
+        meta_types.join(", ")
+    )
+    .unwrap();
+
+    // Each row has either an int or a float value, with "None" in the other.
+    // There are millions of rows, far too many for a source literal, so they
+    // go in a blob of 24 byte rows: type ID, attribute ID and value, all
+    // little endian.
+    let mut type_attributes = Vec::new();
+    if let Some(mut reader) = optional_table("data/dgmTypeAttributes.csv") {
+        for result in reader.records() {
+            let record = result.unwrap();
+            let type_id: u64 = record[0].parse().unwrap();
+            let attribute_id: u64 = record[1].parse().unwrap();
+            let value: f64 = record[3]
+                .parse()
+                .unwrap_or_else(|_| record[2].parse().unwrap_or(0.0));
+            type_attributes.extend_from_slice(&type_id.to_le_bytes());
+            type_attributes.extend_from_slice(&attribute_id.to_le_bytes());
+            type_attributes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    let blob_path = Path::new(&env::var("OUT_DIR").unwrap()).join("type_attributes.bin");
+    std::fs::write(&blob_path, &type_attributes).unwrap();
+    writeln!(
+        &mut file,
+        "static TYPE_ATTRIBUTES: &[u8] = include_bytes!(concat!(env!(\"OUT_DIR\"), \"/type_attributes.bin\"));"
+    )
+    .unwrap();
+
+    let mut type_effects = Vec::new();
+    if let Some(mut reader) = optional_table("data/dgmTypeEffects.csv") {
+        for result in reader.records() {
+            let record = result.unwrap();
+            type_effects.push(format!("({}, {})", &record[0], &record[1]));
+        }
+    }
+    writeln!(
+        &mut file,
+        "static TYPE_EFFECTS: &[(u64, u64)] = &[{}];",
+        type_effects.join(", ")
+    )
+    .unwrap();
+
+    // attributeID, attributeName, description, iconID, defaultValue,
+    // published, displayName, unitID, ...
+    let mut attribute_types = Vec::new();
+    if let Some(mut reader) = optional_table("data/dgmAttributeTypes.csv") {
+        for result in reader.records() {
+            let record = result.unwrap();
+            let display_name = match &record[6] {
+                "" | "None" => "None".to_string(),
+                display_name => format!("Some(r#\"{}\"#)", display_name),
+            };
+            let unit_id: Option<u64> = record[7].parse().ok();
+            let published = &record[5] == "1" || record[5].eq_ignore_ascii_case("true");
+            attribute_types.push(format!(
+                "({}, r#\"{}\"#, {}, {:?}, {})",
+                &record[0], &record[1], display_name, unit_id, published
+            ));
+        }
+    }
+    writeln!(
+        &mut file,
+        "static ATTRIBUTE_TYPES: &[CompiledAttributeType] = &[{}];",
+        attribute_types.join(", ")
+    )
+    .unwrap();
+
+    let mut units = Vec::new();
+    if let Some(mut reader) = optional_table("data/eveUnits.csv") {
+        for result in reader.records() {
+            let record = result.unwrap();
+            let display_name = match &record[2] {
+                "None" => "",
+                display_name => display_name,
+            };
+            units.push(format!(
+                "({}, r#\"{}\"#, r#\"{}\"#)",
+                &record[0], &record[1], display_name
+            ));
+        }
+    }
+    writeln!(
+        &mut file,
+        "static UNITS: &[(u64, &str, &str)] = &[{}];",
+        units.join(", ")
+    )
+    .unwrap();
+}
+
+fn optional_table(path: &str) -> Option<csv::Reader<File>> {
+    println!("cargo::rerun-if-changed={}", path);
+    match csv::ReaderBuilder::new().from_path(path) {
+        Ok(reader) => Some(reader),
+        Err(_) => {
+            println!(
+                "cargo::warning={} not found, leaving it out of the compiled data",
+                path
+            );
+            None
+        }
+    }
 }
@@ -0,0 +1,2165 @@
+// Type data that can be loaded at runtime, so that new SDE releases don't need
+// a rebuild. The data compiled in from data/invTypes.csv (the compiled-data
+// feature) is available as TypeDatabase::compiled().
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Read, Write};
+
+use crate::normalize::normalize_name;
+use crate::snapshot::{SnapshotReader, SnapshotWriter};
+use crate::Quantity;
+
+// The invTypes columns for a single type. Volume is the assembled volume in
+// m3, mass is in kg and base price is in ISK.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeInfo {
+    pub type_id: u64,
+    pub name: String,
+    pub group_id: u64,
+    pub volume: f64,
+    pub capacity: f64,
+    pub mass: f64,
+    pub portion_size: u64,
+    pub base_price: Option<f64>,
+    pub published: bool,
+    pub market_group_id: Option<u64>,
+}
+
+// An invGroups row, e.g. "Shield Extender"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Group {
+    pub group_id: u64,
+    pub category_id: u64,
+    pub name: String,
+    pub published: bool,
+}
+
+// An invCategories row, e.g. "Module"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Category {
+    pub category_id: u64,
+    pub name: String,
+    pub published: bool,
+}
+
+// An invMarketGroups row, e.g. "Shield Extenders" under "Shield" under "Ship
+// Equipment". Only the leaves of the tree have types in them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketGroup {
+    pub market_group_id: u64,
+    // None for the top level groups
+    pub parent_id: Option<u64>,
+    pub name: String,
+    pub has_types: bool,
+}
+
+// A quantity of some other type, e.g. one of the minerals an item
+// reprocesses into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Material {
+    pub type_id: u64,
+    pub quantity: Quantity,
+}
+
+// The industryActivities activityIDs we know about
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum IndustryActivity {
+    Manufacturing,
+    Reaction,
+}
+impl IndustryActivity {
+    fn from_id(activity_id: u64) -> Option<IndustryActivity> {
+        match activity_id {
+            1 => Some(IndustryActivity::Manufacturing),
+            11 => Some(IndustryActivity::Reaction),
+            _ => None,
+        }
+    }
+
+    fn id(&self) -> u64 {
+        match self {
+            IndustryActivity::Manufacturing => 1,
+            IndustryActivity::Reaction => 11,
+        }
+    }
+}
+
+// What one run of a blueprint or reaction formula takes and makes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Blueprint {
+    pub blueprint_type_id: u64,
+    pub activity: IndustryActivity,
+    pub product: Material,
+    pub materials: Vec<Material>,
+}
+
+// An invMetaGroups row, e.g. "Tech II" or "Faction"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetaGroup {
+    pub meta_group_id: u64,
+    pub name: String,
+}
+
+// An invMetaTypes row. Variants point at the type they're a variation of,
+// e.g. Large Shield Extender II at Large Shield Extender I.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MetaType {
+    parent_type_id: Option<u64>,
+    meta_group_id: Option<u64>,
+}
+
+// One of the variants of a type, see TypeDatabase::variations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Variation {
+    pub type_id: u64,
+    pub type_name: String,
+    // The type all the variants are variations of, usually the Tech I one
+    pub parent_type_id: u64,
+    pub meta_group: Option<MetaGroup>,
+    pub meta_level: Option<u64>,
+}
+
+// A dgmAttributeTypes row, e.g. cpu (50), which the game shows as "CPU usage"
+// in teraflops
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeType {
+    pub attribute_id: u64,
+    // The internal name, e.g. "cpu"
+    pub name: String,
+    // The name the game shows, for the attributes that have one
+    pub display_name: Option<String>,
+    pub unit_id: Option<u64>,
+    pub published: bool,
+}
+
+// An eveUnits row, e.g. unit 1, Length, shown as "m"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Unit {
+    pub unit_id: u64,
+    pub name: String,
+    // What goes after a value, e.g. "m", "%" or "GJ". Empty for some units.
+    pub display_name: String,
+}
+
+// An attribute by ID or by name, for TypeDatabase::attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeKey<'a> {
+    Id(u64),
+    Name(&'a str),
+}
+impl From<u64> for AttributeKey<'_> {
+    fn from(attribute_id: u64) -> Self {
+        AttributeKey::Id(attribute_id)
+    }
+}
+impl<'a> From<&'a str> for AttributeKey<'a> {
+    fn from(name: &'a str) -> Self {
+        AttributeKey::Name(name)
+    }
+}
+
+// One attribute value of a type, see TypeDatabase::attribute
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attribute {
+    pub attribute_id: u64,
+    pub value: f64,
+    // Only there when dgmAttributeTypes and eveUnits are loaded
+    pub attribute_type: Option<AttributeType>,
+    pub unit: Option<Unit>,
+}
+impl std::fmt::Display for Attribute {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.attribute_type {
+            Some(attribute_type) => write!(
+                f,
+                "{}: {}",
+                attribute_type
+                    .display_name
+                    .as_deref()
+                    .unwrap_or(&attribute_type.name),
+                self.value
+            )?,
+            None => write!(f, "{}: {}", self.attribute_id, self.value)?,
+        }
+        match &self.unit {
+            Some(unit) if !unit.display_name.is_empty() => write!(f, " {}", unit.display_name),
+            _ => Ok(()),
+        }
+    }
+}
+
+// dgmAttributeTypes IDs for the attributes the crate uses itself
+pub(crate) const ATTRIBUTE_META_LEVEL: u64 = 1692;
+// metaLevelOld, which older dumps have in place of metaLevel
+const ATTRIBUTE_META_LEVEL_OLD: u64 = 633;
+const META_GROUP_TECH_I: u64 = 1;
+// Attributes that the SDE keeps in invTypes rather than dgmTypeAttributes
+const ATTRIBUTE_MASS: u64 = 4;
+const ATTRIBUTE_CAPACITY: u64 = 38;
+const ATTRIBUTE_VOLUME: u64 = 161;
+
+#[derive(Debug, Default, Clone)]
+pub struct TypeDatabase {
+    types: HashMap<u64, TypeInfo>,
+    groups: HashMap<u64, Group>,
+    categories: HashMap<u64, Category>,
+    market_groups: HashMap<u64, MarketGroup>,
+    // Packaged volumes for the types that have one, e.g. ships and
+    // containers. Everything else packages to its normal volume.
+    packaged_volumes: HashMap<u64, f64>,
+    // What one portion of a type reprocesses into, from invTypeMaterials
+    type_materials: HashMap<u64, Vec<Material>>,
+    // Product type ID -> the blueprint that makes it
+    blueprints: HashMap<u64, Blueprint>,
+    meta_groups: HashMap<u64, MetaGroup>,
+    meta_types: HashMap<u64, MetaType>,
+    // Every parent_type_id in meta_types, i.e. the Tech I types that have
+    // variants
+    variation_parents: HashSet<u64>,
+    // Type ID -> attribute ID -> value, from dgmTypeAttributes
+    type_attributes: HashMap<u64, HashMap<u64, f64>>,
+    // Type ID -> effect IDs, from dgmTypeEffects
+    type_effects: HashMap<u64, Vec<u64>>,
+    attribute_types: HashMap<u64, AttributeType>,
+    units: HashMap<u64, Unit>,
+    // Every ID for every name, lowest first. EVE has items with dupe names but
+    // different codes.
+    ids_by_name: HashMap<String, Vec<u64>>,
+    // normalize_name(name) -> name, see canonical_name
+    normalized: HashMap<String, String>,
+}
+
+// EVE has several types with the same name, e.g. an unpublished legacy copy
+// of a ship. The policy decides which ID a name resolves to.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum ResolutionPolicy {
+    // Published types first, then the highest ID
+    #[default]
+    PreferPublished,
+    // Types that are on the market first, then published, then the highest ID
+    PreferMarketListed,
+    PreferHighestId,
+}
+impl ResolutionPolicy {
+    // flags gives (published, market listed) for an ID
+    pub(crate) fn pick_by(&self, type_ids: &[u64], flags: impl Fn(u64) -> (bool, bool)) -> u64 {
+        *type_ids
+            .iter()
+            .max_by_key(|id| {
+                let (published, market_listed) = flags(**id);
+                match self {
+                    ResolutionPolicy::PreferPublished => (published, false, **id),
+                    ResolutionPolicy::PreferMarketListed => (market_listed, published, **id),
+                    ResolutionPolicy::PreferHighestId => (false, false, **id),
+                }
+            })
+            .expect("every name has at least one ID")
+    }
+}
+
+impl TypeDatabase {
+    pub fn new() -> TypeDatabase {
+        TypeDatabase::default()
+    }
+
+    // The data baked in at compile time, built on first use
+    #[cfg(feature = "compiled-data")]
+    pub fn compiled() -> &'static TypeDatabase {
+        static COMPILED: std::sync::OnceLock<TypeDatabase> = std::sync::OnceLock::new();
+        COMPILED.get_or_init(|| {
+            let mut db = TypeDatabase::new();
+            for (id, name) in crate::CODE_TO_ITEM.entries() {
+                let (
+                    group_id,
+                    volume,
+                    capacity,
+                    mass,
+                    portion_size,
+                    base_price,
+                    published,
+                    market_group_id,
+                ) = crate::CODE_TO_INFO[id];
+                db.types.insert(
+                    *id,
+                    TypeInfo {
+                        type_id: *id,
+                        name: name.to_string(),
+                        group_id,
+                        volume,
+                        capacity,
+                        mass,
+                        portion_size,
+                        base_price,
+                        published,
+                        market_group_id,
+                    },
+                );
+            }
+            for (name, ids) in crate::ITEM_TO_CODES.entries() {
+                db.ids_by_name.insert(name.to_string(), ids.to_vec());
+            }
+            for (normalized, name) in crate::NORMALIZED_TO_ITEM.entries() {
+                db.normalized
+                    .insert(normalized.to_string(), name.to_string());
+            }
+            for (group_id, category_id, name, published) in crate::GROUPS {
+                db.groups.insert(
+                    *group_id,
+                    Group {
+                        group_id: *group_id,
+                        category_id: *category_id,
+                        name: name.to_string(),
+                        published: *published,
+                    },
+                );
+            }
+            for (category_id, name, published) in crate::CATEGORIES {
+                db.categories.insert(
+                    *category_id,
+                    Category {
+                        category_id: *category_id,
+                        name: name.to_string(),
+                        published: *published,
+                    },
+                );
+            }
+            for (market_group_id, parent_id, name, has_types) in crate::MARKET_GROUPS {
+                db.market_groups.insert(
+                    *market_group_id,
+                    MarketGroup {
+                        market_group_id: *market_group_id,
+                        parent_id: *parent_id,
+                        name: name.to_string(),
+                        has_types: *has_types,
+                    },
+                );
+            }
+            db.packaged_volumes
+                .extend(crate::PACKAGED_VOLUMES.iter().copied());
+            for (type_id, material_type_id, quantity) in crate::TYPE_MATERIALS {
+                db.type_materials
+                    .entry(*type_id)
+                    .or_default()
+                    .push(Material {
+                        type_id: *material_type_id,
+                        quantity: *quantity,
+                    });
+            }
+            db.add_blueprints(crate::BLUEPRINT_PRODUCTS, crate::BLUEPRINT_MATERIALS);
+            for (meta_group_id, name) in crate::META_GROUPS {
+                db.meta_groups.insert(
+                    *meta_group_id,
+                    MetaGroup {
+                        meta_group_id: *meta_group_id,
+                        name: name.to_string(),
+                    },
+                );
+            }
+            for (type_id, parent_type_id, meta_group_id) in crate::META_TYPES {
+                db.insert_meta_type(
+                    *type_id,
+                    MetaType {
+                        parent_type_id: *parent_type_id,
+                        meta_group_id: *meta_group_id,
+                    },
+                );
+            }
+            for (type_id, effect_id) in crate::TYPE_EFFECTS {
+                db.type_effects
+                    .entry(*type_id)
+                    .or_default()
+                    .push(*effect_id);
+            }
+            // Rows of type ID, attribute ID and value, see build.rs
+            for row in crate::TYPE_ATTRIBUTES.chunks_exact(24) {
+                let type_id = u64::from_le_bytes(row[0..8].try_into().unwrap());
+                let attribute_id = u64::from_le_bytes(row[8..16].try_into().unwrap());
+                let value = f64::from_le_bytes(row[16..24].try_into().unwrap());
+                db.type_attributes
+                    .entry(type_id)
+                    .or_default()
+                    .insert(attribute_id, value);
+            }
+            for (attribute_id, name, display_name, unit_id, published) in crate::ATTRIBUTE_TYPES {
+                db.attribute_types.insert(
+                    *attribute_id,
+                    AttributeType {
+                        attribute_id: *attribute_id,
+                        name: name.to_string(),
+                        display_name: display_name.map(|name| name.to_string()),
+                        unit_id: *unit_id,
+                        published: *published,
+                    },
+                );
+            }
+            for (unit_id, name, display_name) in crate::UNITS {
+                db.units.insert(
+                    *unit_id,
+                    Unit {
+                        unit_id: *unit_id,
+                        name: name.to_string(),
+                        display_name: display_name.to_string(),
+                    },
+                );
+            }
+            db
+        })
+    }
+
+    // Fuzzwork's invTypes.csv, e.g. https://www.fuzzwork.co.uk/dump/latest/invTypes.csv
+    pub fn from_fuzzwork_csv<R: Read>(reader: R) -> Result<TypeDatabase, String> {
+        let table = CsvTable::read(reader, "invTypes")?;
+        let type_id = table.column("typeID")?;
+        let name = table.column("typeName")?;
+        let group_id = table.column("groupID")?;
+        let volume = table.column("volume")?;
+        let capacity = table.column("capacity")?;
+        let mass = table.column("mass")?;
+        let portion_size = table.column("portionSize")?;
+        let base_price = table.column("basePrice")?;
+        let published = table.column("published")?;
+        let market_group_id = table.column("marketGroupID")?;
+
+        let mut db = TypeDatabase::new();
+        for (i, record) in table.records.iter().enumerate() {
+            let row_error = |e| format!("invTypes row {}: {}", i + 1, e);
+            let id = parse_id(&record[type_id]).map_err(row_error)?;
+            let info = TypeInfo {
+                type_id: id,
+                name: record[name].to_string(),
+                group_id: parse_id(&record[group_id]).map_err(row_error)?,
+                volume: parse_f64(&record[volume]),
+                capacity: parse_f64(&record[capacity]),
+                mass: parse_f64(&record[mass]),
+                portion_size: parse_optional_id(&record[portion_size]).unwrap_or(1),
+                base_price: record[base_price].parse().ok(),
+                published: parse_bool(&record[published]),
+                market_group_id: parse_optional_id(&record[market_group_id]),
+            };
+            db.types.insert(id, info);
+        }
+        db.rebuild_indexes();
+        Ok(db)
+    }
+
+    // types.jsonl from CCP's JSON Lines SDE export, one type per line
+    pub fn from_sde_jsonl<R: Read>(reader: R) -> Result<TypeDatabase, String> {
+        #[derive(Deserialize)]
+        struct SdeType {
+            #[serde(rename = "_key")]
+            type_id: u64,
+            name: HashMap<String, String>,
+            #[serde(rename = "groupID")]
+            group_id: u64,
+            #[serde(default)]
+            volume: f64,
+            #[serde(default)]
+            capacity: f64,
+            #[serde(default)]
+            mass: f64,
+            #[serde(rename = "portionSize", default = "default_portion_size")]
+            portion_size: u64,
+            #[serde(rename = "basePrice")]
+            base_price: Option<f64>,
+            #[serde(default)]
+            published: bool,
+            #[serde(rename = "marketGroupID")]
+            market_group_id: Option<u64>,
+            #[serde(rename = "metaGroupID")]
+            meta_group_id: Option<u64>,
+            #[serde(rename = "variationParentTypeID")]
+            parent_type_id: Option<u64>,
+        }
+
+        let mut db = TypeDatabase::new();
+        for (line, sde_type) in read_jsonl::<SdeType, R>(reader, "types.jsonl")? {
+            if sde_type.meta_group_id.is_some() || sde_type.parent_type_id.is_some() {
+                db.insert_meta_type(
+                    sde_type.type_id,
+                    MetaType {
+                        parent_type_id: sde_type.parent_type_id,
+                        meta_group_id: sde_type.meta_group_id,
+                    },
+                );
+            }
+            db.types.insert(
+                sde_type.type_id,
+                TypeInfo {
+                    type_id: sde_type.type_id,
+                    name: english_name(sde_type.name, "types.jsonl", line)?,
+                    group_id: sde_type.group_id,
+                    volume: sde_type.volume,
+                    capacity: sde_type.capacity,
+                    mass: sde_type.mass,
+                    portion_size: sde_type.portion_size,
+                    base_price: sde_type.base_price,
+                    published: sde_type.published,
+                    market_group_id: sde_type.market_group_id,
+                },
+            );
+        }
+        db.rebuild_indexes();
+        Ok(db)
+    }
+
+    // Fuzzwork's invGroups.csv
+    pub fn load_fuzzwork_groups_csv<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        let table = CsvTable::read(reader, "invGroups")?;
+        let group_id = table.column("groupID")?;
+        let category_id = table.column("categoryID")?;
+        let name = table.column("groupName")?;
+        let published = table.column("published")?;
+        for (i, record) in table.records.iter().enumerate() {
+            let row_error = |e| format!("invGroups row {}: {}", i + 1, e);
+            let group = Group {
+                group_id: parse_id(&record[group_id]).map_err(row_error)?,
+                category_id: parse_id(&record[category_id]).map_err(row_error)?,
+                name: record[name].to_string(),
+                published: parse_bool(&record[published]),
+            };
+            self.groups.insert(group.group_id, group);
+        }
+        Ok(())
+    }
+
+    // Fuzzwork's invCategories.csv
+    pub fn load_fuzzwork_categories_csv<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        let table = CsvTable::read(reader, "invCategories")?;
+        let category_id = table.column("categoryID")?;
+        let name = table.column("categoryName")?;
+        let published = table.column("published")?;
+        for (i, record) in table.records.iter().enumerate() {
+            let category = Category {
+                category_id: parse_id(&record[category_id])
+                    .map_err(|e| format!("invCategories row {}: {}", i + 1, e))?,
+                name: record[name].to_string(),
+                published: parse_bool(&record[published]),
+            };
+            self.categories.insert(category.category_id, category);
+        }
+        Ok(())
+    }
+
+    // Fuzzwork's invMarketGroups.csv
+    pub fn load_fuzzwork_market_groups_csv<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        let table = CsvTable::read(reader, "invMarketGroups")?;
+        let market_group_id = table.column("marketGroupID")?;
+        let parent_id = table.column("parentGroupID")?;
+        let name = table.column("marketGroupName")?;
+        let has_types = table.column("hasTypes")?;
+        for (i, record) in table.records.iter().enumerate() {
+            let market_group = MarketGroup {
+                market_group_id: parse_id(&record[market_group_id])
+                    .map_err(|e| format!("invMarketGroups row {}: {}", i + 1, e))?,
+                parent_id: parse_optional_id(&record[parent_id]),
+                name: record[name].to_string(),
+                has_types: parse_bool(&record[has_types]),
+            };
+            self.market_groups
+                .insert(market_group.market_group_id, market_group);
+        }
+        Ok(())
+    }
+
+    // Fuzzwork's invVolumes.csv, the packaged volumes of ships and containers
+    pub fn load_fuzzwork_volumes_csv<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        let table = CsvTable::read(reader, "invVolumes")?;
+        let type_id = table.column("typeID")?;
+        let volume = table.column("volume")?;
+        for (i, record) in table.records.iter().enumerate() {
+            let id = parse_id(&record[type_id])
+                .map_err(|e| format!("invVolumes row {}: {}", i + 1, e))?;
+            self.packaged_volumes.insert(id, parse_f64(&record[volume]));
+        }
+        Ok(())
+    }
+
+    // Fuzzwork's invTypeMaterials.csv
+    pub fn load_fuzzwork_type_materials_csv<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        let table = CsvTable::read(reader, "invTypeMaterials")?;
+        let type_id = table.column("typeID")?;
+        let material_type_id = table.column("materialTypeID")?;
+        let quantity = table.column("quantity")?;
+        for (i, record) in table.records.iter().enumerate() {
+            let row_error = |e| format!("invTypeMaterials row {}: {}", i + 1, e);
+            let material = Material {
+                type_id: parse_id(&record[material_type_id]).map_err(row_error)?,
+                quantity: parse_id(&record[quantity]).map_err(row_error)?,
+            };
+            self.type_materials
+                .entry(parse_id(&record[type_id]).map_err(row_error)?)
+                .or_default()
+                .push(material);
+        }
+        Ok(())
+    }
+
+    // Fuzzwork's industryActivityProducts.csv and
+    // industryActivityMaterials.csv. Only manufacturing and reactions are
+    // kept.
+    pub fn load_fuzzwork_industry_csv<R: Read, S: Read>(
+        &mut self,
+        products: R,
+        materials: S,
+    ) -> Result<(), String> {
+        let products = read_industry_csv(products, "industryActivityProducts", "productTypeID")?;
+        let materials =
+            read_industry_csv(materials, "industryActivityMaterials", "materialTypeID")?;
+        self.add_blueprints(&products, &materials);
+        Ok(())
+    }
+
+    // Fuzzwork's invMetaGroups.csv
+    pub fn load_fuzzwork_meta_groups_csv<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        let table = CsvTable::read(reader, "invMetaGroups")?;
+        let meta_group_id = table.column("metaGroupID")?;
+        let name = table.column("metaGroupName")?;
+        for (i, record) in table.records.iter().enumerate() {
+            let meta_group = MetaGroup {
+                meta_group_id: parse_id(&record[meta_group_id])
+                    .map_err(|e| format!("invMetaGroups row {}: {}", i + 1, e))?,
+                name: record[name].to_string(),
+            };
+            self.meta_groups
+                .insert(meta_group.meta_group_id, meta_group);
+        }
+        Ok(())
+    }
+
+    fn insert_meta_type(&mut self, type_id: u64, meta_type: MetaType) {
+        self.variation_parents.extend(meta_type.parent_type_id);
+        self.meta_types.insert(type_id, meta_type);
+    }
+
+    // Fuzzwork's invMetaTypes.csv
+    pub fn load_fuzzwork_meta_types_csv<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        let table = CsvTable::read(reader, "invMetaTypes")?;
+        let type_id = table.column("typeID")?;
+        let parent_type_id = table.column("parentTypeID")?;
+        let meta_group_id = table.column("metaGroupID")?;
+        for (i, record) in table.records.iter().enumerate() {
+            self.insert_meta_type(
+                parse_id(&record[type_id])
+                    .map_err(|e| format!("invMetaTypes row {}: {}", i + 1, e))?,
+                MetaType {
+                    parent_type_id: parse_optional_id(&record[parent_type_id]),
+                    meta_group_id: parse_optional_id(&record[meta_group_id]),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    // Fuzzwork's dgmTypeAttributes.csv
+    pub fn load_fuzzwork_type_attributes_csv<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        let table = CsvTable::read(reader, "dgmTypeAttributes")?;
+        let type_id = table.column("typeID")?;
+        let attribute_id = table.column("attributeID")?;
+        let value_int = table.column("valueInt")?;
+        let value_float = table.column("valueFloat")?;
+        for (i, record) in table.records.iter().enumerate() {
+            let row_error = |e| format!("dgmTypeAttributes row {}: {}", i + 1, e);
+            // Each row has one or the other, with "None" in the other column
+            let value = match record[value_float].parse::<f64>() {
+                Ok(value) => value,
+                Err(_) => parse_f64(&record[value_int]),
+            };
+            self.type_attributes
+                .entry(parse_id(&record[type_id]).map_err(row_error)?)
+                .or_default()
+                .insert(parse_id(&record[attribute_id]).map_err(row_error)?, value);
+        }
+        Ok(())
+    }
+
+    // Fuzzwork's dgmTypeEffects.csv
+    pub fn load_fuzzwork_type_effects_csv<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        let table = CsvTable::read(reader, "dgmTypeEffects")?;
+        let type_id = table.column("typeID")?;
+        let effect_id = table.column("effectID")?;
+        for (i, record) in table.records.iter().enumerate() {
+            let row_error = |e| format!("dgmTypeEffects row {}: {}", i + 1, e);
+            self.type_effects
+                .entry(parse_id(&record[type_id]).map_err(row_error)?)
+                .or_default()
+                .push(parse_id(&record[effect_id]).map_err(row_error)?);
+        }
+        Ok(())
+    }
+
+    // Fuzzwork's dgmAttributeTypes.csv
+    pub fn load_fuzzwork_attribute_types_csv<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        let table = CsvTable::read(reader, "dgmAttributeTypes")?;
+        let attribute_id = table.column("attributeID")?;
+        let name = table.column("attributeName")?;
+        let display_name = table.column("displayName")?;
+        let unit_id = table.column("unitID")?;
+        let published = table.column("published")?;
+        for (i, record) in table.records.iter().enumerate() {
+            let attribute_type = AttributeType {
+                attribute_id: parse_id(&record[attribute_id])
+                    .map_err(|e| format!("dgmAttributeTypes row {}: {}", i + 1, e))?,
+                name: record[name].to_string(),
+                display_name: parse_optional_string(&record[display_name]),
+                unit_id: parse_optional_id(&record[unit_id]),
+                published: parse_bool(&record[published]),
+            };
+            self.attribute_types
+                .insert(attribute_type.attribute_id, attribute_type);
+        }
+        Ok(())
+    }
+
+    // Fuzzwork's eveUnits.csv
+    pub fn load_fuzzwork_units_csv<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        let table = CsvTable::read(reader, "eveUnits")?;
+        let unit_id = table.column("unitID")?;
+        let name = table.column("unitName")?;
+        let display_name = table.column("displayName")?;
+        for (i, record) in table.records.iter().enumerate() {
+            let unit = Unit {
+                unit_id: parse_id(&record[unit_id])
+                    .map_err(|e| format!("eveUnits row {}: {}", i + 1, e))?,
+                name: record[name].to_string(),
+                display_name: parse_optional_string(&record[display_name]).unwrap_or_default(),
+            };
+            self.units.insert(unit.unit_id, unit);
+        }
+        Ok(())
+    }
+
+    // groups.jsonl from the JSON Lines SDE
+    pub fn load_sde_groups_jsonl<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        #[derive(Deserialize)]
+        struct SdeGroup {
+            #[serde(rename = "_key")]
+            group_id: u64,
+            #[serde(rename = "categoryID")]
+            category_id: u64,
+            name: HashMap<String, String>,
+            #[serde(default)]
+            published: bool,
+        }
+
+        for (line, sde_group) in read_jsonl::<SdeGroup, R>(reader, "groups.jsonl")? {
+            self.groups.insert(
+                sde_group.group_id,
+                Group {
+                    group_id: sde_group.group_id,
+                    category_id: sde_group.category_id,
+                    name: english_name(sde_group.name, "groups.jsonl", line)?,
+                    published: sde_group.published,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    // categories.jsonl from the JSON Lines SDE
+    pub fn load_sde_categories_jsonl<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        #[derive(Deserialize)]
+        struct SdeCategory {
+            #[serde(rename = "_key")]
+            category_id: u64,
+            name: HashMap<String, String>,
+            #[serde(default)]
+            published: bool,
+        }
+
+        for (line, sde_category) in read_jsonl::<SdeCategory, R>(reader, "categories.jsonl")? {
+            self.categories.insert(
+                sde_category.category_id,
+                Category {
+                    category_id: sde_category.category_id,
+                    name: english_name(sde_category.name, "categories.jsonl", line)?,
+                    published: sde_category.published,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    // marketGroups.jsonl from the JSON Lines SDE
+    pub fn load_sde_market_groups_jsonl<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        #[derive(Deserialize)]
+        struct SdeMarketGroup {
+            #[serde(rename = "_key")]
+            market_group_id: u64,
+            #[serde(rename = "parentGroupID")]
+            parent_id: Option<u64>,
+            name: HashMap<String, String>,
+            #[serde(rename = "hasTypes", default)]
+            has_types: bool,
+        }
+
+        for (line, sde_market_group) in
+            read_jsonl::<SdeMarketGroup, R>(reader, "marketGroups.jsonl")?
+        {
+            self.market_groups.insert(
+                sde_market_group.market_group_id,
+                MarketGroup {
+                    market_group_id: sde_market_group.market_group_id,
+                    parent_id: sde_market_group.parent_id,
+                    name: english_name(sde_market_group.name, "marketGroups.jsonl", line)?,
+                    has_types: sde_market_group.has_types,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    // typeMaterials.jsonl from the JSON Lines SDE
+    pub fn load_sde_type_materials_jsonl<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        #[derive(Deserialize)]
+        struct SdeMaterial {
+            #[serde(rename = "materialTypeID")]
+            type_id: u64,
+            quantity: Quantity,
+        }
+        #[derive(Deserialize)]
+        struct SdeTypeMaterials {
+            #[serde(rename = "_key")]
+            type_id: u64,
+            materials: Vec<SdeMaterial>,
+        }
+
+        for (_, sde_type_materials) in
+            read_jsonl::<SdeTypeMaterials, R>(reader, "typeMaterials.jsonl")?
+        {
+            self.type_materials.insert(
+                sde_type_materials.type_id,
+                sde_type_materials
+                    .materials
+                    .iter()
+                    .map(|material| Material {
+                        type_id: material.type_id,
+                        quantity: material.quantity,
+                    })
+                    .collect(),
+            );
+        }
+        Ok(())
+    }
+
+    // blueprints.jsonl from the JSON Lines SDE
+    pub fn load_sde_blueprints_jsonl<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        #[derive(Deserialize)]
+        struct SdeMaterial {
+            #[serde(rename = "typeID")]
+            type_id: u64,
+            quantity: Quantity,
+        }
+        #[derive(Deserialize)]
+        struct SdeActivity {
+            #[serde(default)]
+            materials: Vec<SdeMaterial>,
+            #[serde(default)]
+            products: Vec<SdeMaterial>,
+        }
+        #[derive(Deserialize)]
+        struct SdeBlueprint {
+            #[serde(rename = "_key")]
+            blueprint_type_id: u64,
+            activities: HashMap<String, SdeActivity>,
+        }
+
+        let mut products = Vec::new();
+        let mut materials = Vec::new();
+        for (_, sde_blueprint) in read_jsonl::<SdeBlueprint, R>(reader, "blueprints.jsonl")? {
+            for (name, activity) in sde_blueprint.activities {
+                let activity_id = match name.as_str() {
+                    "manufacturing" => IndustryActivity::Manufacturing.id(),
+                    "reaction" => IndustryActivity::Reaction.id(),
+                    _ => continue,
+                };
+                for product in activity.products {
+                    products.push((
+                        sde_blueprint.blueprint_type_id,
+                        activity_id,
+                        product.type_id,
+                        product.quantity,
+                    ));
+                }
+                for material in activity.materials {
+                    materials.push((
+                        sde_blueprint.blueprint_type_id,
+                        activity_id,
+                        material.type_id,
+                        material.quantity,
+                    ));
+                }
+            }
+        }
+        self.add_blueprints(&products, &materials);
+        Ok(())
+    }
+
+    // Both take (blueprint type ID, activity ID, type ID, quantity) rows.
+    // When more than one blueprint makes a product the lowest blueprint type
+    // ID wins, so that loading order doesn't matter.
+    fn add_blueprints(
+        &mut self,
+        products: &[(u64, u64, u64, Quantity)],
+        materials: &[(u64, u64, u64, Quantity)],
+    ) {
+        let mut by_blueprint: HashMap<(u64, u64), Vec<Material>> = HashMap::new();
+        for (blueprint_type_id, activity_id, type_id, quantity) in materials {
+            by_blueprint
+                .entry((*blueprint_type_id, *activity_id))
+                .or_default()
+                .push(Material {
+                    type_id: *type_id,
+                    quantity: *quantity,
+                });
+        }
+        for (blueprint_type_id, activity_id, type_id, quantity) in products {
+            let activity = match IndustryActivity::from_id(*activity_id) {
+                Some(activity) => activity,
+                None => continue,
+            };
+            let blueprint = Blueprint {
+                blueprint_type_id: *blueprint_type_id,
+                activity,
+                product: Material {
+                    type_id: *type_id,
+                    quantity: *quantity,
+                },
+                materials: by_blueprint
+                    .get(&(*blueprint_type_id, *activity_id))
+                    .cloned()
+                    .unwrap_or_default(),
+            };
+            match self.blueprints.get(type_id) {
+                Some(existing) if existing.blueprint_type_id <= *blueprint_type_id => (),
+                _ => {
+                    self.blueprints.insert(*type_id, blueprint);
+                }
+            }
+        }
+    }
+
+    // metaGroups.jsonl from the JSON Lines SDE. The meta types themselves are
+    // in types.jsonl.
+    pub fn load_sde_meta_groups_jsonl<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        #[derive(Deserialize)]
+        struct SdeMetaGroup {
+            #[serde(rename = "_key")]
+            meta_group_id: u64,
+            name: HashMap<String, String>,
+        }
+
+        for (line, sde_meta_group) in read_jsonl::<SdeMetaGroup, R>(reader, "metaGroups.jsonl")? {
+            self.meta_groups.insert(
+                sde_meta_group.meta_group_id,
+                MetaGroup {
+                    meta_group_id: sde_meta_group.meta_group_id,
+                    name: english_name(sde_meta_group.name, "metaGroups.jsonl", line)?,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    // typeDogma.jsonl from the JSON Lines SDE
+    pub fn load_sde_type_dogma_jsonl<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        #[derive(Deserialize)]
+        struct SdeAttribute {
+            #[serde(rename = "attributeID")]
+            attribute_id: u64,
+            value: f64,
+        }
+        #[derive(Deserialize)]
+        struct SdeEffect {
+            #[serde(rename = "effectID")]
+            effect_id: u64,
+        }
+        #[derive(Deserialize)]
+        struct SdeTypeDogma {
+            #[serde(rename = "_key")]
+            type_id: u64,
+            #[serde(rename = "dogmaAttributes", default)]
+            attributes: Vec<SdeAttribute>,
+            #[serde(rename = "dogmaEffects", default)]
+            effects: Vec<SdeEffect>,
+        }
+
+        for (_, sde_type_dogma) in read_jsonl::<SdeTypeDogma, R>(reader, "typeDogma.jsonl")? {
+            self.type_attributes.insert(
+                sde_type_dogma.type_id,
+                sde_type_dogma
+                    .attributes
+                    .iter()
+                    .map(|attribute| (attribute.attribute_id, attribute.value))
+                    .collect(),
+            );
+            self.type_effects.insert(
+                sde_type_dogma.type_id,
+                sde_type_dogma
+                    .effects
+                    .iter()
+                    .map(|effect| effect.effect_id)
+                    .collect(),
+            );
+        }
+        Ok(())
+    }
+
+    // dogmaAttributes.jsonl from the JSON Lines SDE
+    pub fn load_sde_dogma_attributes_jsonl<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        #[derive(Deserialize)]
+        struct SdeDogmaAttribute {
+            #[serde(rename = "_key")]
+            attribute_id: u64,
+            name: String,
+            #[serde(rename = "displayName")]
+            display_name: Option<HashMap<String, String>>,
+            #[serde(rename = "unitID")]
+            unit_id: Option<u64>,
+            #[serde(default)]
+            published: bool,
+        }
+
+        for (_, sde_attribute) in
+            read_jsonl::<SdeDogmaAttribute, R>(reader, "dogmaAttributes.jsonl")?
+        {
+            self.attribute_types.insert(
+                sde_attribute.attribute_id,
+                AttributeType {
+                    attribute_id: sde_attribute.attribute_id,
+                    name: sde_attribute.name,
+                    display_name: sde_attribute
+                        .display_name
+                        .and_then(|mut names| names.remove("en"))
+                        .filter(|name| !name.is_empty()),
+                    unit_id: sde_attribute.unit_id,
+                    published: sde_attribute.published,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    // dogmaUnits.jsonl from the JSON Lines SDE
+    pub fn load_sde_dogma_units_jsonl<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        #[derive(Deserialize)]
+        struct SdeDogmaUnit {
+            #[serde(rename = "_key")]
+            unit_id: u64,
+            name: String,
+            #[serde(rename = "displayName")]
+            display_name: Option<HashMap<String, String>>,
+        }
+
+        for (_, sde_unit) in read_jsonl::<SdeDogmaUnit, R>(reader, "dogmaUnits.jsonl")? {
+            self.units.insert(
+                sde_unit.unit_id,
+                Unit {
+                    unit_id: sde_unit.unit_id,
+                    name: sde_unit.name,
+                    display_name: sde_unit
+                        .display_name
+                        .and_then(|mut names| names.remove("en"))
+                        .unwrap_or_default(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    // A compact binary copy of the database, written by write_snapshot
+    pub fn from_snapshot<R: Read>(reader: R) -> Result<TypeDatabase, String> {
+        let mut r = SnapshotReader::new(reader)?;
+        let mut db = TypeDatabase::new();
+        let count = r.u64()?;
+        for _ in 0..count {
+            let info = TypeInfo {
+                type_id: r.u64()?,
+                name: r.string()?,
+                group_id: r.u64()?,
+                volume: r.f64()?,
+                capacity: r.f64()?,
+                mass: r.f64()?,
+                portion_size: r.u64()?,
+                base_price: r.optional_f64()?,
+                published: r.bool()?,
+                market_group_id: r.optional_u64()?,
+            };
+            db.types.insert(info.type_id, info);
+        }
+        let count = r.u64()?;
+        for _ in 0..count {
+            let group = Group {
+                group_id: r.u64()?,
+                category_id: r.u64()?,
+                name: r.string()?,
+                published: r.bool()?,
+            };
+            db.groups.insert(group.group_id, group);
+        }
+        let count = r.u64()?;
+        for _ in 0..count {
+            let category = Category {
+                category_id: r.u64()?,
+                name: r.string()?,
+                published: r.bool()?,
+            };
+            db.categories.insert(category.category_id, category);
+        }
+        let count = r.u64()?;
+        for _ in 0..count {
+            let market_group = MarketGroup {
+                market_group_id: r.u64()?,
+                parent_id: r.optional_u64()?,
+                name: r.string()?,
+                has_types: r.bool()?,
+            };
+            db.market_groups
+                .insert(market_group.market_group_id, market_group);
+        }
+        let count = r.u64()?;
+        for _ in 0..count {
+            let id = r.u64()?;
+            db.packaged_volumes.insert(id, r.f64()?);
+        }
+        let count = r.u64()?;
+        for _ in 0..count {
+            let id = r.u64()?;
+            let material_count = r.u64()?;
+            let mut materials = Vec::new();
+            for _ in 0..material_count {
+                materials.push(Material {
+                    type_id: r.u64()?,
+                    quantity: r.u64()?,
+                });
+            }
+            db.type_materials.insert(id, materials);
+        }
+        let count = r.u64()?;
+        for _ in 0..count {
+            let blueprint_type_id = r.u64()?;
+            let activity_id = r.u64()?;
+            let activity = IndustryActivity::from_id(activity_id)
+                .ok_or(format!("unknown activity {} in snapshot", activity_id))?;
+            let product = Material {
+                type_id: r.u64()?,
+                quantity: r.u64()?,
+            };
+            let material_count = r.u64()?;
+            let mut materials = Vec::new();
+            for _ in 0..material_count {
+                materials.push(Material {
+                    type_id: r.u64()?,
+                    quantity: r.u64()?,
+                });
+            }
+            db.blueprints.insert(
+                product.type_id,
+                Blueprint {
+                    blueprint_type_id,
+                    activity,
+                    product,
+                    materials,
+                },
+            );
+        }
+        let count = r.u64()?;
+        for _ in 0..count {
+            let meta_group = MetaGroup {
+                meta_group_id: r.u64()?,
+                name: r.string()?,
+            };
+            db.meta_groups.insert(meta_group.meta_group_id, meta_group);
+        }
+        let count = r.u64()?;
+        for _ in 0..count {
+            let id = r.u64()?;
+            let meta_type = MetaType {
+                parent_type_id: r.optional_u64()?,
+                meta_group_id: r.optional_u64()?,
+            };
+            db.insert_meta_type(id, meta_type);
+        }
+        let count = r.u64()?;
+        for _ in 0..count {
+            let id = r.u64()?;
+            let attribute_count = r.u64()?;
+            let mut attributes = HashMap::new();
+            for _ in 0..attribute_count {
+                let attribute_id = r.u64()?;
+                attributes.insert(attribute_id, r.f64()?);
+            }
+            db.type_attributes.insert(id, attributes);
+        }
+        let count = r.u64()?;
+        for _ in 0..count {
+            let id = r.u64()?;
+            let effect_count = r.u64()?;
+            let mut effects = Vec::new();
+            for _ in 0..effect_count {
+                effects.push(r.u64()?);
+            }
+            db.type_effects.insert(id, effects);
+        }
+        let count = r.u64()?;
+        for _ in 0..count {
+            let attribute_type = AttributeType {
+                attribute_id: r.u64()?,
+                name: r.string()?,
+                display_name: r.optional_string()?,
+                unit_id: r.optional_u64()?,
+                published: r.bool()?,
+            };
+            db.attribute_types
+                .insert(attribute_type.attribute_id, attribute_type);
+        }
+        let count = r.u64()?;
+        for _ in 0..count {
+            let unit = Unit {
+                unit_id: r.u64()?,
+                name: r.string()?,
+                display_name: r.string()?,
+            };
+            db.units.insert(unit.unit_id, unit);
+        }
+        db.rebuild_indexes();
+        Ok(db)
+    }
+
+    pub fn write_snapshot<W: Write>(&self, writer: W) -> Result<(), String> {
+        let mut w = SnapshotWriter::new(writer)?;
+        // Sorted so that the same data always gives the same bytes
+        let mut ids: Vec<&u64> = self.types.keys().collect();
+        ids.sort();
+        w.u64(ids.len() as u64)?;
+        for id in ids {
+            let info = &self.types[id];
+            w.u64(info.type_id)?;
+            w.string(&info.name)?;
+            w.u64(info.group_id)?;
+            w.f64(info.volume)?;
+            w.f64(info.capacity)?;
+            w.f64(info.mass)?;
+            w.u64(info.portion_size)?;
+            w.optional_f64(info.base_price)?;
+            w.bool(info.published)?;
+            w.optional_u64(info.market_group_id)?;
+        }
+        let mut group_ids: Vec<&u64> = self.groups.keys().collect();
+        group_ids.sort();
+        w.u64(group_ids.len() as u64)?;
+        for id in group_ids {
+            let group = &self.groups[id];
+            w.u64(group.group_id)?;
+            w.u64(group.category_id)?;
+            w.string(&group.name)?;
+            w.bool(group.published)?;
+        }
+        let mut category_ids: Vec<&u64> = self.categories.keys().collect();
+        category_ids.sort();
+        w.u64(category_ids.len() as u64)?;
+        for id in category_ids {
+            let category = &self.categories[id];
+            w.u64(category.category_id)?;
+            w.string(&category.name)?;
+            w.bool(category.published)?;
+        }
+        let mut market_group_ids: Vec<&u64> = self.market_groups.keys().collect();
+        market_group_ids.sort();
+        w.u64(market_group_ids.len() as u64)?;
+        for id in market_group_ids {
+            let market_group = &self.market_groups[id];
+            w.u64(market_group.market_group_id)?;
+            w.optional_u64(market_group.parent_id)?;
+            w.string(&market_group.name)?;
+            w.bool(market_group.has_types)?;
+        }
+        let mut packaged_ids: Vec<&u64> = self.packaged_volumes.keys().collect();
+        packaged_ids.sort();
+        w.u64(packaged_ids.len() as u64)?;
+        for id in packaged_ids {
+            w.u64(*id)?;
+            w.f64(self.packaged_volumes[id])?;
+        }
+        let mut material_ids: Vec<&u64> = self.type_materials.keys().collect();
+        material_ids.sort();
+        w.u64(material_ids.len() as u64)?;
+        for id in material_ids {
+            let materials = &self.type_materials[id];
+            w.u64(*id)?;
+            w.u64(materials.len() as u64)?;
+            for material in materials {
+                w.u64(material.type_id)?;
+                w.u64(material.quantity)?;
+            }
+        }
+        let mut product_ids: Vec<&u64> = self.blueprints.keys().collect();
+        product_ids.sort();
+        w.u64(product_ids.len() as u64)?;
+        for id in product_ids {
+            let blueprint = &self.blueprints[id];
+            w.u64(blueprint.blueprint_type_id)?;
+            w.u64(blueprint.activity.id())?;
+            w.u64(blueprint.product.type_id)?;
+            w.u64(blueprint.product.quantity)?;
+            w.u64(blueprint.materials.len() as u64)?;
+            for material in blueprint.materials.iter() {
+                w.u64(material.type_id)?;
+                w.u64(material.quantity)?;
+            }
+        }
+        let mut meta_group_ids: Vec<&u64> = self.meta_groups.keys().collect();
+        meta_group_ids.sort();
+        w.u64(meta_group_ids.len() as u64)?;
+        for id in meta_group_ids {
+            w.u64(*id)?;
+            w.string(&self.meta_groups[id].name)?;
+        }
+        let mut meta_type_ids: Vec<&u64> = self.meta_types.keys().collect();
+        meta_type_ids.sort();
+        w.u64(meta_type_ids.len() as u64)?;
+        for id in meta_type_ids {
+            let meta_type = &self.meta_types[id];
+            w.u64(*id)?;
+            w.optional_u64(meta_type.parent_type_id)?;
+            w.optional_u64(meta_type.meta_group_id)?;
+        }
+        let mut attribute_type_ids: Vec<&u64> = self.type_attributes.keys().collect();
+        attribute_type_ids.sort();
+        w.u64(attribute_type_ids.len() as u64)?;
+        for id in attribute_type_ids {
+            let mut attributes: Vec<(&u64, &f64)> = self.type_attributes[id].iter().collect();
+            attributes.sort_by_key(|(attribute_id, _)| **attribute_id);
+            w.u64(*id)?;
+            w.u64(attributes.len() as u64)?;
+            for (attribute_id, value) in attributes {
+                w.u64(*attribute_id)?;
+                w.f64(*value)?;
+            }
+        }
+        let mut effect_type_ids: Vec<&u64> = self.type_effects.keys().collect();
+        effect_type_ids.sort();
+        w.u64(effect_type_ids.len() as u64)?;
+        for id in effect_type_ids {
+            let effects = &self.type_effects[id];
+            w.u64(*id)?;
+            w.u64(effects.len() as u64)?;
+            for effect_id in effects {
+                w.u64(*effect_id)?;
+            }
+        }
+        let mut attribute_ids: Vec<&u64> = self.attribute_types.keys().collect();
+        attribute_ids.sort();
+        w.u64(attribute_ids.len() as u64)?;
+        for id in attribute_ids {
+            let attribute_type = &self.attribute_types[id];
+            w.u64(*id)?;
+            w.string(&attribute_type.name)?;
+            w.optional_string(attribute_type.display_name.as_deref())?;
+            w.optional_u64(attribute_type.unit_id)?;
+            w.bool(attribute_type.published)?;
+        }
+        let mut unit_ids: Vec<&u64> = self.units.keys().collect();
+        unit_ids.sort();
+        w.u64(unit_ids.len() as u64)?;
+        for id in unit_ids {
+            let unit = &self.units[id];
+            w.u64(*id)?;
+            w.string(&unit.name)?;
+            w.string(&unit.display_name)?;
+        }
+        w.finish()
+    }
+
+    fn rebuild_indexes(&mut self) {
+        self.ids_by_name.clear();
+        for (id, info) in self.types.iter() {
+            self.ids_by_name
+                .entry(info.name.clone())
+                .or_default()
+                .push(*id);
+        }
+        for ids in self.ids_by_name.values_mut() {
+            ids.sort();
+        }
+
+        // Several names can normalise to the same key, e.g. ones that only
+        // differ in case. Go through them in sorted order so that the same
+        // one wins every time, matching build.rs.
+        self.normalized.clear();
+        let mut names: Vec<&String> = self.ids_by_name.keys().collect();
+        names.sort();
+        for name in names {
+            self.normalized
+                .entry(normalize_name(name))
+                .or_insert_with(|| name.clone());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    pub fn type_name(&self, type_id: u64) -> Option<&str> {
+        self.types.get(&type_id).map(|info| info.name.as_str())
+    }
+
+    // Everything invTypes has on a type
+    pub fn type_info(&self, type_id: u64) -> Option<&TypeInfo> {
+        self.types.get(&type_id)
+    }
+
+    // The SDE spelling of a type name. An exact match first, then the case,
+    // quote, dash and whitespace insensitive index.
+    pub fn canonical_name(&self, type_name: &str) -> Option<&str> {
+        if let Some((name, _)) = self.ids_by_name.get_key_value(type_name) {
+            return Some(name);
+        }
+        self.normalized
+            .get(&normalize_name(type_name))
+            .map(|name| name.as_str())
+    }
+
+    // Every type ID with the given name, lowest first
+    pub fn type_ids(&self, type_name: &str) -> &[u64] {
+        match self.canonical_name(type_name) {
+            Some(name) => &self.ids_by_name[name],
+            None => &[],
+        }
+    }
+
+    // The policy's pick out of all the IDs for a name
+    pub fn resolve(&self, type_name: &str, policy: ResolutionPolicy) -> Option<u64> {
+        let type_ids = self.type_ids(type_name);
+        if type_ids.is_empty() {
+            return None;
+        }
+        Some(policy.pick_by(type_ids, |id| match self.types.get(&id) {
+            Some(info) => (info.published, info.market_group_id.is_some()),
+            None => (false, false),
+        }))
+    }
+
+    // Every name along with all of its IDs, in no particular order
+    pub fn names(&self) -> impl Iterator<Item = (&str, &[u64])> {
+        self.ids_by_name
+            .iter()
+            .map(|(name, ids)| (name.as_str(), ids.as_slice()))
+    }
+
+    pub fn group(&self, group_id: u64) -> Option<&Group> {
+        self.groups.get(&group_id)
+    }
+
+    pub fn category(&self, category_id: u64) -> Option<&Category> {
+        self.categories.get(&category_id)
+    }
+
+    pub fn group_of(&self, type_id: u64) -> Option<&Group> {
+        self.group(self.types.get(&type_id)?.group_id)
+    }
+
+    pub fn category_of(&self, type_id: u64) -> Option<&Category> {
+        self.category(self.group_of(type_id)?.category_id)
+    }
+
+    // All of the groups and categories, in no particular order
+    pub fn groups(&self) -> impl Iterator<Item = &Group> {
+        self.groups.values()
+    }
+
+    pub fn categories(&self) -> impl Iterator<Item = &Category> {
+        self.categories.values()
+    }
+
+    // The types in a group, in no particular order
+    pub fn group_members(&self, group_id: u64) -> impl Iterator<Item = &TypeInfo> {
+        self.types
+            .values()
+            .filter(move |info| info.group_id == group_id)
+    }
+
+    // The groups in a category, in no particular order
+    pub fn category_members(&self, category_id: u64) -> impl Iterator<Item = &Group> {
+        self.groups
+            .values()
+            .filter(move |group| group.category_id == category_id)
+    }
+
+    // The types in every group of a category, in no particular order
+    pub fn category_types(&self, category_id: u64) -> impl Iterator<Item = &TypeInfo> {
+        self.types.values().filter(move |info| {
+            self.group(info.group_id)
+                .is_some_and(|group| group.category_id == category_id)
+        })
+    }
+
+    pub fn market_group(&self, market_group_id: u64) -> Option<&MarketGroup> {
+        self.market_groups.get(&market_group_id)
+    }
+
+    // Every market group, in no particular order
+    pub fn market_groups(&self) -> impl Iterator<Item = &MarketGroup> {
+        self.market_groups.values()
+    }
+
+    // The market groups directly under parent_id, or the top level ones for
+    // None, sorted by name like the market browser
+    pub fn market_group_children(&self, parent_id: Option<u64>) -> Vec<&MarketGroup> {
+        let mut children: Vec<&MarketGroup> = self
+            .market_groups
+            .values()
+            .filter(|market_group| market_group.parent_id == parent_id)
+            .collect();
+        children.sort_by(|a, b| (&a.name, a.market_group_id).cmp(&(&b.name, b.market_group_id)));
+        children
+    }
+
+    // The market groups a type is listed under, top level first, e.g. Ship
+    // Equipment, Shield, Shield Extenders. Empty for types that aren't on the
+    // market.
+    pub fn market_path(&self, type_id: u64) -> Vec<&MarketGroup> {
+        let mut path = Vec::new();
+        let mut next = self
+            .types
+            .get(&type_id)
+            .and_then(|info| info.market_group_id);
+        while let Some(market_group) = next.and_then(|id| self.market_groups.get(&id)) {
+            // A bad parentGroupID could send us round in circles
+            if path.len() > self.market_groups.len() {
+                break;
+            }
+            path.push(market_group);
+            next = market_group.parent_id;
+        }
+        path.reverse();
+        path
+    }
+
+    // The volume of a packaged type, which is only different from
+    // TypeInfo::volume for ships and containers
+    pub fn packaged_volume(&self, type_id: u64) -> Option<f64> {
+        match self.packaged_volumes.get(&type_id) {
+            Some(volume) => Some(*volume),
+            None => self.types.get(&type_id).map(|info| info.volume),
+        }
+    }
+
+    // What one portion of a type reprocesses into, see TypeInfo::portion_size
+    pub fn type_materials(&self, type_id: u64) -> &[Material] {
+        match self.type_materials.get(&type_id) {
+            Some(materials) => materials,
+            None => &[],
+        }
+    }
+
+    // The blueprint or reaction formula that makes a type
+    pub fn blueprint_for(&self, product_type_id: u64) -> Option<&Blueprint> {
+        self.blueprints.get(&product_type_id)
+    }
+
+    pub fn meta_group(&self, meta_group_id: u64) -> Option<&MetaGroup> {
+        self.meta_groups.get(&meta_group_id)
+    }
+
+    // Tech I types have no invMetaTypes row of their own, so the parent of
+    // any variant is taken to be Tech I
+    pub fn meta_group_of(&self, type_id: u64) -> Option<&MetaGroup> {
+        match self.meta_types.get(&type_id) {
+            Some(meta_type) => self.meta_group(meta_type.meta_group_id?),
+            None if self.variation_parents.contains(&type_id) => self.meta_group(META_GROUP_TECH_I),
+            None => None,
+        }
+    }
+
+    // metaLevel, or metaLevelOld for dumps that don't have it
+    pub fn meta_level(&self, type_id: u64) -> Option<u64> {
+        self.attribute_value(type_id, ATTRIBUTE_META_LEVEL)
+            .or_else(|| self.attribute_value(type_id, ATTRIBUTE_META_LEVEL_OLD))
+            .map(|level| level as u64)
+    }
+
+    // The type and all of its variants, e.g. every Large Shield Extender from
+    // Tech I to Officer, by meta level and then type ID. Works from any of
+    // the variants. Empty for types the database doesn't know.
+    pub fn variations(&self, type_id: u64) -> Vec<Variation> {
+        if !self.types.contains_key(&type_id) {
+            return Vec::new();
+        }
+        let parent_type_id = self
+            .meta_types
+            .get(&type_id)
+            .and_then(|meta_type| meta_type.parent_type_id)
+            .unwrap_or(type_id);
+
+        let mut type_ids = vec![parent_type_id];
+        for (id, meta_type) in self.meta_types.iter() {
+            if meta_type.parent_type_id == Some(parent_type_id) && *id != parent_type_id {
+                type_ids.push(*id);
+            }
+        }
+        let mut variations: Vec<Variation> = type_ids
+            .into_iter()
+            .map(|id| Variation {
+                type_id: id,
+                type_name: self.type_name(id).unwrap_or("Unknown").to_string(),
+                parent_type_id,
+                meta_group: self.meta_group_of(id).cloned(),
+                meta_level: self.meta_level(id),
+            })
+            .collect();
+        variations.sort_by_key(|variation| (variation.meta_level, variation.type_id));
+        variations
+    }
+
+    pub fn attribute_type(&self, attribute_id: u64) -> Option<&AttributeType> {
+        self.attribute_types.get(&attribute_id)
+    }
+
+    // By internal name like "cpu" or, failing that, the name the game shows
+    // like "CPU usage", ignoring case. Display names aren't unique, so the
+    // lowest attribute ID wins.
+    pub fn attribute_type_by_name(&self, name: &str) -> Option<&AttributeType> {
+        let attribute_types = self.attribute_types.values();
+        attribute_types
+            .clone()
+            .filter(|attribute_type| attribute_type.name.eq_ignore_ascii_case(name))
+            .min_by_key(|attribute_type| attribute_type.attribute_id)
+            .or_else(|| {
+                attribute_types
+                    .filter(|attribute_type| {
+                        attribute_type
+                            .display_name
+                            .as_deref()
+                            .is_some_and(|display_name| display_name.eq_ignore_ascii_case(name))
+                    })
+                    .min_by_key(|attribute_type| attribute_type.attribute_id)
+            })
+    }
+
+    pub fn unit(&self, unit_id: u64) -> Option<&Unit> {
+        self.units.get(&unit_id)
+    }
+
+    // A single attribute of a type by ID or name, e.g. db.attribute(3841,
+    // "cpu"). Mass, capacity and volume come from invTypes. Looking up by
+    // name needs dgmAttributeTypes loaded.
+    pub fn attribute<'a>(
+        &self,
+        type_id: u64,
+        attribute: impl Into<AttributeKey<'a>>,
+    ) -> Option<Attribute> {
+        let attribute_id = match attribute.into() {
+            AttributeKey::Id(attribute_id) => attribute_id,
+            AttributeKey::Name(name) => self.attribute_type_by_name(name)?.attribute_id,
+        };
+        let value = match attribute_id {
+            ATTRIBUTE_MASS => self.types.get(&type_id).map(|info| info.mass),
+            ATTRIBUTE_CAPACITY => self.types.get(&type_id).map(|info| info.capacity),
+            ATTRIBUTE_VOLUME => self.types.get(&type_id).map(|info| info.volume),
+            _ => None,
+        };
+        let value = value.or_else(|| self.attribute_value(type_id, attribute_id))?;
+        let attribute_type = self.attribute_type(attribute_id).cloned();
+        let unit = attribute_type
+            .as_ref()
+            .and_then(|attribute_type| self.unit(attribute_type.unit_id?))
+            .cloned();
+        Some(Attribute {
+            attribute_id,
+            value,
+            attribute_type,
+            unit,
+        })
+    }
+
+    // Every attribute of a type, by attribute ID
+    pub fn attributes(&self, type_id: u64) -> Vec<Attribute> {
+        let mut attribute_ids: Vec<u64> = self
+            .type_attributes
+            .get(&type_id)
+            .map(|attributes| attributes.keys().copied().collect())
+            .unwrap_or_default();
+        if self.types.contains_key(&type_id) {
+            for attribute_id in [ATTRIBUTE_MASS, ATTRIBUTE_CAPACITY, ATTRIBUTE_VOLUME] {
+                if !attribute_ids.contains(&attribute_id) {
+                    attribute_ids.push(attribute_id);
+                }
+            }
+        }
+        attribute_ids.sort();
+        attribute_ids
+            .into_iter()
+            .filter_map(|attribute_id| self.attribute(type_id, attribute_id))
+            .collect()
+    }
+
+    pub(crate) fn attribute_value(&self, type_id: u64, attribute_id: u64) -> Option<f64> {
+        self.type_attributes
+            .get(&type_id)?
+            .get(&attribute_id)
+            .copied()
+    }
+
+    pub(crate) fn has_effect(&self, type_id: u64, effect_id: u64) -> bool {
+        self.type_effects
+            .get(&type_id)
+            .is_some_and(|effects| effects.contains(&effect_id))
+    }
+
+    pub(crate) fn volume(&self, type_id: u64) -> f64 {
+        self.types.get(&type_id).map_or(0.0, |info| info.volume)
+    }
+
+    pub(crate) fn capacity(&self, type_id: u64) -> f64 {
+        self.types.get(&type_id).map_or(0.0, |info| info.capacity)
+    }
+}
+
+// A CSV file with a header row, looked up by column name since the SDE dumps
+// don't promise a column order
+struct CsvTable {
+    name: &'static str,
+    columns: HashMap<String, usize>,
+    records: Vec<csv::StringRecord>,
+}
+impl CsvTable {
+    fn read<R: Read>(reader: R, name: &'static str) -> Result<CsvTable, String> {
+        let mut reader = csv::ReaderBuilder::new().from_reader(reader);
+        let headers = reader
+            .headers()
+            .map_err(|e| format!("reading {} header: {}", name, e))?;
+        let columns = headers
+            .iter()
+            .enumerate()
+            .map(|(i, h)| (h.to_string(), i))
+            .collect();
+        let records = reader
+            .records()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("reading {}: {}", name, e))?;
+        Ok(CsvTable {
+            name,
+            columns,
+            records,
+        })
+    }
+
+    fn column(&self, column: &str) -> Result<usize, String> {
+        self.columns
+            .get(column)
+            .copied()
+            .ok_or(format!("{} is missing the {} column", self.name, column))
+    }
+}
+
+fn parse_id(s: &str) -> Result<u64, String> {
+    s.parse().map_err(|e| format!("parsing ID {}: {}", s, e))
+}
+
+// The fuzzwork dumps write missing values as "None"
+fn parse_optional_id(s: &str) -> Option<u64> {
+    s.parse().ok()
+}
+
+fn parse_optional_string(s: &str) -> Option<String> {
+    match s {
+        "" | "None" => None,
+        s => Some(s.to_string()),
+    }
+}
+
+fn parse_f64(s: &str) -> f64 {
+    s.parse().unwrap_or(0.0)
+}
+
+// (blueprint type ID, activity ID, type ID, quantity) rows from one of the
+// industryActivity tables
+fn read_industry_csv<R: Read>(
+    reader: R,
+    name: &'static str,
+    type_column: &str,
+) -> Result<Vec<(u64, u64, u64, Quantity)>, String> {
+    let table = CsvTable::read(reader, name)?;
+    let blueprint_id = table.column("typeID")?;
+    let activity_id = table.column("activityID")?;
+    let type_id = table.column(type_column)?;
+    let quantity = table.column("quantity")?;
+    let mut rows = Vec::new();
+    for (i, record) in table.records.iter().enumerate() {
+        let row_error = |e| format!("{} row {}: {}", name, i + 1, e);
+        rows.push((
+            parse_id(&record[blueprint_id]).map_err(row_error)?,
+            parse_id(&record[activity_id]).map_err(row_error)?,
+            parse_id(&record[type_id]).map_err(row_error)?,
+            parse_id(&record[quantity]).map_err(row_error)?,
+        ));
+    }
+    Ok(rows)
+}
+
+// Every non-empty line of a JSON Lines file along with its line number
+fn read_jsonl<T: DeserializeOwned, R: Read>(
+    reader: R,
+    name: &str,
+) -> Result<Vec<(usize, T)>, String> {
+    let mut rows = Vec::new();
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line.map_err(|e| format!("reading {}: {}", name, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row =
+            serde_json::from_str(&line).map_err(|e| format!("{} line {}: {}", name, i + 1, e))?;
+        rows.push((i + 1, row));
+    }
+    Ok(rows)
+}
+
+// The SDE names things in several languages, we only use English
+fn english_name(
+    mut names: HashMap<String, String>,
+    file: &str,
+    line: usize,
+) -> Result<String, String> {
+    names
+        .remove("en")
+        .ok_or_else(|| format!("{} line {}: no English name", file, line))
+}
+
+fn default_portion_size() -> u64 {
+    1
+}
+
+fn parse_bool(s: &str) -> bool {
+    s == "1" || s.eq_ignore_ascii_case("true")
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // Every type the module tests use, so they can share one database
+    pub(crate) const INV_TYPES: &str = "typeID,groupID,typeName,description,mass,volume,capacity,portionSize,raceID,basePrice,published,marketGroupID,iconID,soundID,graphicID
+3841,38,Large Shield Extender II,,1000,10,0,1,None,0,1,553,1044,None,3841
+24698,419,Drake,,13500000,252000,450,1,1,0,1,471,None,20068,24698
+28661,419,Drake,,13500000,252000,450,1,1,0,0,None,None,20068,24698
+28700,419,Drake,,13500000,252000,450,1,1,0,0,None,None,20068,24698
+1877,511,Rapid Light Missile Launcher II,,1000,10,0.75,1,None,0,1,641,None,None,None
+210,384,Scourge Light Missile,,1000,0.015,0,100,None,0,1,920,None,None,None
+209,385,Scourge Heavy Missile,,1000,0.03,0,100,None,0,1,927,None,None,None
+34,18,Tritanium,,0,0.01,0,1,None,2,1,1857,22,None,None
+35,18,Pyerite,,0,0.01,0,1,None,8,1,1857,400,None,None
+1230,462,Veldspar,,1e+35,0.1,0,100,None,2,1,518,1271,None,None
+28659,900,Paladin,,92245000,495000,1125,1,4,0,1,1081,None,20061,28659
+3540,62,Large Armor Repairer II,,500,50,0,1,None,0,1,1051,80,None,None
+3057,53,Mega Pulse Laser II,,2000,20,1,1,None,0,1,570,350,None,2880
+2488,100,Hobgoblin II,,3000,5,0,1,None,0,1,838,None,None,2488
+26082,773,Large Trimark Armor Pump I,,200,20,0,1,None,0,1,1214,None,None,None
+31055,773,Medium Trimark Armor Pump I,,200,10,0,1,None,0,1,1215,None,None,None
+12822,86,Multifrequency L,,1,1,0,1,None,0,1,1007,None,None,None
+12818,86,Multifrequency M,,1,1,0,1,None,0,1,1008,None,None,None
+3839,38,Large Shield Extender I,,1000,10,0,1,None,0,1,553,1044,None,3839
+3416,1209,Shield Operation,,0,0.01,0,1,None,0,1,1747,33,None,None
+3419,1209,Shield Management,,0,0.01,0,1,None,0,1,1747,33,None,None
+3425,1209,Shield Upgrades,,0,0.01,0,1,None,0,1,1747,33,None,None
+3413,1216,Power Grid Management,,0,0.01,0,1,None,0,1,368,33,None,None
+";
+    pub(crate) const INV_GROUPS: &str = "groupID,categoryID,groupName,iconID,useBasePrice,anchored,anchorable,fittableNonSingleton,published
+38,7,Shield Extender,None,0,0,0,0,1
+419,6,Combat Battlecruiser,None,0,0,0,0,1
+";
+    pub(crate) const INV_CATEGORIES: &str = "categoryID,categoryName,iconID,published
+6,Ship,None,1
+7,Module,67,1
+";
+
+    pub(crate) const INV_MARKET_GROUPS: &str =
+        "marketGroupID,parentGroupID,marketGroupName,description,iconID,hasTypes
+9,None,Ship Equipment,\"Modules, rigs and the like\",77,0
+4,None,Ships,,1443,0
+551,9,Shield,,None,0
+553,551,Shield Extenders,,1044,1
+1361,4,Battlecruisers,,None,0
+471,1361,Caldari,,None,1
+";
+
+    pub(crate) const INV_VOLUMES: &str = "typeID,volume
+24698,15000
+28661,15000
+";
+
+    // A Drake blueprint, and copying which is ignored
+    pub(crate) const INDUSTRY_ACTIVITY_PRODUCTS: &str = "typeID,activityID,productTypeID,quantity
+24699,1,24698,1
+24699,5,24699,1
+";
+    pub(crate) const INDUSTRY_ACTIVITY_MATERIALS: &str = "typeID,activityID,materialTypeID,quantity
+24699,1,3841,3
+24699,5,34,10
+";
+
+    pub(crate) const INV_META_GROUPS: &str = "metaGroupID,metaGroupName,description,iconID
+1,Tech I,,None
+2,Tech II,,None
+4,Faction,,None
+";
+    // The unpublished Drakes as made up faction variants
+    pub(crate) const INV_META_TYPES: &str = "typeID,parentTypeID,metaGroupID
+28661,24698,4
+28700,24698,4
+";
+    pub(crate) const DGM_TYPE_ATTRIBUTES: &str = "typeID,attributeID,valueInt,valueFloat
+24698,633,0,None
+28661,633,None,8.0
+28700,633,6,None
+28700,1692,5,None
+3841,14,None,0
+";
+    pub(crate) const DGM_ATTRIBUTE_TYPES: &str = "attributeID,attributeName,description,iconID,defaultValue,published,displayName,unitID,stackable,highIsGood,categoryID
+14,hiSlots,number of high power slots,293,0,1,High Slots,None,1,1,4
+161,volume,Space occupied,67,0,1,Volume,9,1,1,7
+633,metaLevelOld,The meta level of the item,None,0,0,None,140,1,1,7
+1692,metaLevel,The meta level of the item,None,0,1,Meta Level,140,1,1,7
+1640,techLevelDisplay,Made up to share a display name with metaLevel,None,0,1,Meta Level,None,1,1,7
+";
+    pub(crate) const EVE_UNITS: &str = "unitID,unitName,displayName,description
+9,Volume,m3,Cubic meter
+140,Level,None,Level
+";
+
+    #[test]
+    fn fuzzwork_csv() {
+        let db = TypeDatabase::from_fuzzwork_csv(INV_TYPES.as_bytes()).unwrap();
+        assert_eq!(db.len(), 23);
+        assert_eq!(db.type_name(3841), Some("Large Shield Extender II"));
+        assert_eq!(
+            db.canonical_name("large shield extender ii"),
+            Some("Large Shield Extender II")
+        );
+        assert_eq!(db.type_ids("Drake"), &[24698, 28661, 28700]);
+        let info = db.type_info(24698).unwrap();
+        assert_eq!(info.group_id, 419);
+        assert_eq!(info.mass, 13500000.0);
+        assert_eq!(info.volume, 252000.0);
+        assert_eq!(info.portion_size, 1);
+        assert_eq!(info.base_price, Some(0.0));
+        assert_eq!(info.market_group_id, Some(471));
+        assert_eq!(db.type_info(28661).unwrap().market_group_id, None);
+        assert_eq!(db.type_info(1), None);
+        assert_eq!(
+            db.resolve("Drake", ResolutionPolicy::PreferPublished),
+            Some(24698)
+        );
+        assert_eq!(
+            db.resolve("Drake", ResolutionPolicy::PreferHighestId),
+            Some(28700)
+        );
+        assert_eq!(
+            db.resolve("Not A Real Type", ResolutionPolicy::default()),
+            None
+        );
+        assert!(TypeDatabase::from_fuzzwork_csv("typeID,typeName\n1,Foo\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn sde_jsonl() {
+        let jsonl = r#"{"_key": 3841, "groupID": 38, "name": {"en": "Large Shield Extender II", "de": "Großer Schildextender II"}, "volume": 10.0, "published": true, "marketGroupID": 553}
+
+{"_key": 28661, "groupID": 419, "name": {"en": "Drake"}, "volume": 252000.0, "capacity": 450.0, "published": false}
+"#;
+        let db = TypeDatabase::from_sde_jsonl(jsonl.as_bytes()).unwrap();
+        assert_eq!(db.len(), 2);
+        assert_eq!(
+            db.resolve("large shield extender ii", ResolutionPolicy::default()),
+            Some(3841)
+        );
+        assert_eq!(db.capacity(28661), 450.0);
+        let info = db.type_info(3841).unwrap();
+        assert_eq!(info.group_id, 38);
+        assert_eq!(info.portion_size, 1);
+        assert_eq!(info.base_price, None);
+        assert!(TypeDatabase::from_sde_jsonl(r#"{"_key": 1}"#.as_bytes()).is_err());
+
+        let mut db = db;
+        db.load_sde_groups_jsonl(
+            r#"{"_key": 419, "categoryID": 6, "name": {"en": "Combat Battlecruiser"}, "published": true}"#
+                .as_bytes(),
+        )
+        .unwrap();
+        db.load_sde_categories_jsonl(r#"{"_key": 6, "name": {"en": "Ship"}}"#.as_bytes())
+            .unwrap();
+        assert_eq!(db.group_of(28661).unwrap().name, "Combat Battlecruiser");
+        assert_eq!(db.category_of(28661).unwrap().name, "Ship");
+        assert!(!db.category(6).unwrap().published);
+
+        db.load_sde_blueprints_jsonl(
+            r#"{"_key": 28662, "activities": {"copying": {"time": 100}, "manufacturing": {"materials": [{"typeID": 34, "quantity": 100}], "products": [{"typeID": 28661, "quantity": 1}], "time": 600}}}"#
+                .as_bytes(),
+        )
+        .unwrap();
+        let blueprint = db.blueprint_for(28661).unwrap();
+        assert_eq!(blueprint.blueprint_type_id, 28662);
+        assert_eq!(blueprint.activity, IndustryActivity::Manufacturing);
+        assert_eq!(
+            blueprint.materials,
+            vec![Material {
+                type_id: 34,
+                quantity: 100
+            }]
+        );
+
+        db.load_sde_dogma_attributes_jsonl(
+            r#"{"_key": 161, "name": "volume", "displayName": {"en": "Volume"}, "unitID": 9, "published": true}
+{"_key": 633, "name": "metaLevelOld", "published": false}"#
+                .as_bytes(),
+        )
+        .unwrap();
+        db.load_sde_dogma_units_jsonl(
+            r#"{"_key": 9, "name": "Volume", "displayName": {"en": "m3"}}"#.as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(
+            db.attribute(28661, "volume").unwrap().to_string(),
+            "Volume: 252000 m3"
+        );
+        assert_eq!(db.attribute_type(633).unwrap().display_name, None);
+    }
+
+    #[test]
+    fn variations() {
+        let mut db = TypeDatabase::from_fuzzwork_csv(INV_TYPES.as_bytes()).unwrap();
+        db.load_fuzzwork_meta_groups_csv(INV_META_GROUPS.as_bytes())
+            .unwrap();
+        db.load_fuzzwork_meta_types_csv(INV_META_TYPES.as_bytes())
+            .unwrap();
+        db.load_fuzzwork_type_attributes_csv(DGM_TYPE_ATTRIBUTES.as_bytes())
+            .unwrap();
+
+        let variations = db.variations(28661);
+        let ids: Vec<u64> = variations.iter().map(|v| v.type_id).collect();
+        assert_eq!(ids, vec![24698, 28700, 28661]);
+        assert_eq!(variations[0].meta_group.as_ref().unwrap().name, "Tech I");
+        assert_eq!(variations[1].meta_group.as_ref().unwrap().name, "Faction");
+        // metaLevel wins over metaLevelOld
+        assert_eq!(variations[1].meta_level, Some(5));
+        assert_eq!(variations[2].meta_level, Some(8));
+        assert!(variations.iter().all(|v| v.parent_type_id == 24698));
+        assert_eq!(db.variations(24698), variations);
+
+        assert_eq!(db.variations(3841).len(), 1);
+        assert!(db.variations(1).is_empty());
+        assert_eq!(db.meta_group_of(28700).unwrap().meta_group_id, 4);
+        assert_eq!(db.meta_group_of(24698).unwrap().meta_group_id, 1);
+        assert_eq!(db.meta_group_of(3841), None);
+    }
+
+    #[test]
+    fn attributes() {
+        let mut db = TypeDatabase::from_fuzzwork_csv(INV_TYPES.as_bytes()).unwrap();
+        db.load_fuzzwork_type_attributes_csv(DGM_TYPE_ATTRIBUTES.as_bytes())
+            .unwrap();
+        // Values work without the attribute types, by ID only
+        assert_eq!(db.attribute(28661, 633).unwrap().value, 8.0);
+        assert_eq!(db.attribute(28661, "metaLevelOld"), None);
+
+        db.load_fuzzwork_attribute_types_csv(DGM_ATTRIBUTE_TYPES.as_bytes())
+            .unwrap();
+        db.load_fuzzwork_units_csv(EVE_UNITS.as_bytes()).unwrap();
+        let meta_level = db.attribute(28661, "metalevelold").unwrap();
+        assert_eq!(meta_level.attribute_id, 633);
+        assert_eq!(meta_level.unit.as_ref().unwrap().display_name, "");
+        assert_eq!(meta_level.to_string(), "metaLevelOld: 8");
+        // Volume is an invTypes column
+        let volume = db.attribute(3841, "Volume").unwrap();
+        assert_eq!(volume.value, 10.0);
+        assert_eq!(volume.to_string(), "Volume: 10 m3");
+        assert_eq!(db.attribute(3841, "High Slots").unwrap().value, 0.0);
+        assert_eq!(db.attribute(3841, "cpu"), None);
+        assert_eq!(db.attribute(1, 161), None);
+
+        let ids: Vec<u64> = db
+            .attributes(3841)
+            .iter()
+            .map(|attribute| attribute.attribute_id)
+            .collect();
+        assert_eq!(ids, vec![4, 14, 38, 161]);
+        assert!(db.attributes(1).is_empty());
+        assert_eq!(
+            db.attribute_type(14).unwrap().display_name.as_deref(),
+            Some("High Slots")
+        );
+        assert_eq!(db.attribute_type(633).unwrap().display_name, None);
+        assert_eq!(db.unit(9).unwrap().name, "Volume");
+
+        // Display names are shared, so the lowest attribute ID is picked
+        assert_eq!(
+            db.attribute_type_by_name("meta level")
+                .unwrap()
+                .attribute_id,
+            1640
+        );
+        assert_eq!(
+            db.attribute_type_by_name("metalevel").unwrap().attribute_id,
+            1692
+        );
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let mut db = TypeDatabase::from_fuzzwork_csv(INV_TYPES.as_bytes()).unwrap();
+        db.load_fuzzwork_groups_csv(INV_GROUPS.as_bytes()).unwrap();
+        db.load_fuzzwork_categories_csv(INV_CATEGORIES.as_bytes())
+            .unwrap();
+        db.load_fuzzwork_market_groups_csv(INV_MARKET_GROUPS.as_bytes())
+            .unwrap();
+        db.load_fuzzwork_volumes_csv(INV_VOLUMES.as_bytes())
+            .unwrap();
+        db.load_fuzzwork_type_materials_csv(
+            "typeID,materialTypeID,quantity\n3841,34,2580\n3841,35,540\n".as_bytes(),
+        )
+        .unwrap();
+        db.load_fuzzwork_industry_csv(
+            INDUSTRY_ACTIVITY_PRODUCTS.as_bytes(),
+            INDUSTRY_ACTIVITY_MATERIALS.as_bytes(),
+        )
+        .unwrap();
+        db.load_fuzzwork_meta_groups_csv(INV_META_GROUPS.as_bytes())
+            .unwrap();
+        db.load_fuzzwork_meta_types_csv(INV_META_TYPES.as_bytes())
+            .unwrap();
+        db.load_fuzzwork_type_attributes_csv(DGM_TYPE_ATTRIBUTES.as_bytes())
+            .unwrap();
+        db.load_fuzzwork_type_effects_csv("typeID,effectID,isDefault\n3841,13,0\n".as_bytes())
+            .unwrap();
+        db.load_fuzzwork_attribute_types_csv(DGM_ATTRIBUTE_TYPES.as_bytes())
+            .unwrap();
+        db.load_fuzzwork_units_csv(EVE_UNITS.as_bytes()).unwrap();
+        let mut bytes = Vec::new();
+        db.write_snapshot(&mut bytes).unwrap();
+        let loaded = TypeDatabase::from_snapshot(bytes.as_slice()).unwrap();
+        assert_eq!(loaded.types, db.types);
+        assert_eq!(loaded.ids_by_name, db.ids_by_name);
+        assert_eq!(loaded.normalized, db.normalized);
+        assert_eq!(loaded.groups, db.groups);
+        assert_eq!(loaded.categories, db.categories);
+        assert_eq!(loaded.market_groups, db.market_groups);
+        assert_eq!(loaded.packaged_volumes, db.packaged_volumes);
+        assert_eq!(loaded.type_materials, db.type_materials);
+        assert_eq!(loaded.blueprints, db.blueprints);
+        assert_eq!(loaded.meta_groups, db.meta_groups);
+        assert_eq!(loaded.meta_types, db.meta_types);
+        assert_eq!(loaded.variation_parents, db.variation_parents);
+        assert_eq!(loaded.type_attributes, db.type_attributes);
+        assert_eq!(loaded.type_effects, db.type_effects);
+        assert_eq!(loaded.attribute_types, db.attribute_types);
+        assert_eq!(loaded.units, db.units);
+
+        assert!(TypeDatabase::from_snapshot(&bytes[..bytes.len() - 1]).is_err());
+        assert!(TypeDatabase::from_snapshot("not a snapshot".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,334 @@
+// Comparing two item lists, e.g. a hangar against a doctrine fit
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{quantity_delta, DetailedItem, Item, ItemWithId, ModuleState, Quantity, QuantityDelta};
+
+// What diff and format_x need from the items of a list. Items with a type ID
+// are matched on it, the rest on their name. Modules in a different state or
+// with a different charge loaded are different items.
+pub trait DiffItem {
+    fn type_name(&self) -> &str;
+    fn type_id(&self) -> Option<u64>;
+    fn quantity(&self) -> Quantity;
+    fn state(&self) -> Option<ModuleState> {
+        None
+    }
+    fn charge(&self) -> Option<&str> {
+        None
+    }
+    // The same item with another quantity, for ItemDiff::missing
+    fn with_quantity(&self, quantity: Quantity) -> Self
+    where
+        Self: Sized;
+}
+impl DiffItem for Item {
+    fn type_name(&self) -> &str {
+        &self.type_name
+    }
+    fn type_id(&self) -> Option<u64> {
+        None
+    }
+    fn quantity(&self) -> Quantity {
+        self.quantity
+    }
+    fn with_quantity(&self, quantity: Quantity) -> Item {
+        Item {
+            quantity,
+            ..self.clone()
+        }
+    }
+}
+impl DiffItem for DetailedItem {
+    fn type_name(&self) -> &str {
+        &self.type_name
+    }
+    fn type_id(&self) -> Option<u64> {
+        None
+    }
+    fn quantity(&self) -> Quantity {
+        self.quantity
+    }
+    fn state(&self) -> Option<ModuleState> {
+        self.state
+    }
+    fn charge(&self) -> Option<&str> {
+        self.charge.as_ref().map(|charge| charge.type_name.as_str())
+    }
+    fn with_quantity(&self, quantity: Quantity) -> DetailedItem {
+        DetailedItem {
+            quantity,
+            ..self.clone()
+        }
+    }
+}
+impl DiffItem for ItemWithId {
+    fn type_name(&self) -> &str {
+        &self.type_name
+    }
+    fn type_id(&self) -> Option<u64> {
+        Some(self.type_id)
+    }
+    fn quantity(&self) -> Quantity {
+        self.quantity
+    }
+    fn with_quantity(&self, quantity: Quantity) -> ItemWithId {
+        ItemWithId {
+            quantity,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+enum TypeKey {
+    TypeId(u64),
+    Name(String),
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct DiffKey {
+    type_key: TypeKey,
+    state: Option<ModuleState>,
+    charge: Option<String>,
+}
+
+// The item the way an EFT fit writes it, e.g. "Mega Pulse Laser II,
+// Multifrequency L /OFFLINE"
+fn describe<T: DiffItem>(item: &T) -> String {
+    let mut description = item.type_name().to_string();
+    if let Some(charge) = item.charge() {
+        description.push_str(", ");
+        description.push_str(charge);
+    }
+    if let Some(state) = item.state() {
+        description.push(' ');
+        description.push_str(state.marker());
+    }
+    description
+}
+
+// One item whose total differs between the lists
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DiffEntry<T> {
+    // The first of the items that were added up, for its name and type ID
+    pub item: T,
+    pub left: Quantity,
+    pub right: Quantity,
+    // right - left
+    pub delta: QuantityDelta,
+}
+impl<T: DiffItem> std::fmt::Display for DiffEntry<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} -> {} ({:+})",
+            describe(&self.item),
+            self.left,
+            self.right,
+            self.delta
+        )
+    }
+}
+
+// Each list takes the items in the order they first appear, left list
+// first. Items with the same total on both sides aren't in any of them.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ItemDiff<T> {
+    // Only in the right list
+    pub added: Vec<DiffEntry<T>>,
+    // Only in the left list
+    pub removed: Vec<DiffEntry<T>>,
+    // In both, with different totals
+    pub changed: Vec<DiffEntry<T>>,
+}
+impl<T: DiffItem> ItemDiff<T> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    // What the left list needs to match the right one: everything added,
+    // and the shortfall of everything that went up. format_x turns it into
+    // multibuy.
+    pub fn missing(&self) -> Vec<T> {
+        self.added
+            .iter()
+            .chain(self.changed.iter().filter(|entry| entry.delta > 0))
+            .map(|entry| entry.item.with_quantity(entry.right - entry.left))
+            .collect()
+    }
+}
+impl<T: DiffItem> std::fmt::Display for ItemDiff<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for entry in self.added.iter() {
+            writeln!(f, "+ {} x{}", describe(&entry.item), entry.right)?;
+        }
+        for entry in self.removed.iter() {
+            writeln!(f, "- {} x{}", describe(&entry.item), entry.left)?;
+        }
+        for entry in self.changed.iter() {
+            writeln!(f, "~ {}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+// Adds up each list by type ID, or name for items without one, and module
+// state and charge, and compares the totals. Think of left as what there is
+// and right as what there should be: added is what's missing from left
+// entirely.
+pub fn diff<T: DiffItem + Clone>(left: &[T], right: &[T]) -> Result<ItemDiff<T>, String> {
+    // Every key in the order it first appears, with the first item for it
+    let mut keys: Vec<(DiffKey, T)> = Vec::new();
+    let mut totals = |items: &[T]| -> Result<HashMap<DiffKey, Quantity>, String> {
+        let mut totals = HashMap::new();
+        for item in items {
+            let key = DiffKey {
+                type_key: match item.type_id() {
+                    Some(type_id) => TypeKey::TypeId(type_id),
+                    None => TypeKey::Name(item.type_name().to_string()),
+                },
+                state: item.state(),
+                charge: item.charge().map(|charge| charge.to_string()),
+            };
+            if !keys.iter().any(|(k, _)| *k == key) {
+                keys.push((key.clone(), item.clone()));
+            }
+            let total: &mut Quantity = totals.entry(key).or_default();
+            *total = total
+                .checked_add(item.quantity())
+                .ok_or(format!("{} quantity overflows", item.type_name()))?;
+        }
+        Ok(totals)
+    };
+    let left_totals = totals(left)?;
+    let right_totals = totals(right)?;
+
+    let mut diff = ItemDiff {
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+    };
+    for (key, item) in keys {
+        let left = left_totals.get(&key).copied().unwrap_or(0);
+        let right = right_totals.get(&key).copied().unwrap_or(0);
+        if left == right {
+            continue;
+        }
+        let entry = DiffEntry {
+            delta: quantity_delta(left, right).ok_or(format!(
+                "{} quantity difference overflows",
+                item.type_name()
+            ))?,
+            item,
+            left,
+            right,
+        };
+        match (left, right) {
+            (0, _) => diff.added.push(entry),
+            (_, 0) => diff.removed.push(entry),
+            _ => diff.changed.push(entry),
+        }
+    }
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::item_with_id;
+    use crate::{format_x, parse_detailed};
+
+    fn item(type_name: &str, quantity: Quantity) -> Item {
+        Item {
+            type_name: type_name.to_string(),
+            quantity,
+        }
+    }
+
+    #[test]
+    fn diffs() {
+        let hangar = vec![
+            item("Paladin", 1),
+            item("Large Armor Repairer II", 1),
+            item("Navy Cap Booster 800", 20),
+            item("Large Armor Repairer II", 1),
+            item("Tritanium", 100),
+        ];
+        let doctrine = vec![
+            item("Paladin", 1),
+            item("Large Armor Repairer II", 3),
+            item("Navy Cap Booster 800", 10),
+            item("Mega Pulse Laser II", 4),
+        ];
+
+        let d = diff(&hangar, &doctrine).unwrap();
+        assert_eq!(
+            d.changed,
+            vec![
+                DiffEntry {
+                    item: item("Large Armor Repairer II", 1),
+                    left: 2,
+                    right: 3,
+                    delta: 1,
+                },
+                DiffEntry {
+                    item: item("Navy Cap Booster 800", 20),
+                    left: 20,
+                    right: 10,
+                    delta: -10,
+                },
+            ]
+        );
+        assert_eq!(d.removed[0].item.type_name, "Tritanium");
+        assert_eq!(d.added[0].delta, 4);
+        assert_eq!(
+            d.to_string(),
+            "+ Mega Pulse Laser II x4
+- Tritanium x100
+~ Large Armor Repairer II 2 -> 3 (+1)
+~ Navy Cap Booster 800 20 -> 10 (-10)
+"
+        );
+        assert_eq!(
+            format_x(d.missing()),
+            "Mega Pulse Laser II x4\nLarge Armor Repairer II x1\n"
+        );
+        assert!(diff(&doctrine, &doctrine).unwrap().is_empty());
+
+        // Type IDs tell apart items with the same name
+        let d = diff(
+            &[item_with_id("Drake", 24698, 1)],
+            &[item_with_id("Drake", 28661, 1)],
+        )
+        .unwrap();
+        assert_eq!(d.added[0].item.type_id, 28661);
+        assert_eq!(d.removed[0].item.type_id, 24698);
+        // Missing items keep their type IDs
+        assert_eq!(d.missing(), vec![item_with_id("Drake", 28661, 1)]);
+
+        assert!(diff(&[item("Tritanium", Quantity::MAX)], &[]).is_err());
+    }
+
+    #[test]
+    fn module_states() {
+        let online =
+            parse_detailed("Large Armor Repairer II\nMega Pulse Laser II, Multifrequency L")
+                .unwrap();
+        let offline = parse_detailed(
+            "Large Armor Repairer II /OFFLINE\nMega Pulse Laser II, Multifrequency M",
+        )
+        .unwrap();
+        let d = diff(&online, &offline).unwrap();
+        assert_eq!(
+            d.to_string(),
+            "+ Large Armor Repairer II /OFFLINE x1
++ Mega Pulse Laser II, Multifrequency M x1
+- Large Armor Repairer II x1
+- Mega Pulse Laser II, Multifrequency L x1
+"
+        );
+        assert_eq!(d.added[0].item.state, Some(ModuleState::Offline));
+        assert!(diff(&offline, &offline).unwrap().is_empty());
+    }
+}
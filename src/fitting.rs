@@ -0,0 +1,860 @@
+// Checking an EFT fit against what its hull can actually take
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    charges_per_load, fuzzy, DetailedItem, Fit, FitHeader, ItemWithId, Quantity, ResolutionPolicy,
+    TypeDatabase,
+};
+
+// invCategories and invGroups IDs for sorting a flat list into a fit
+const CATEGORY_SHIP: u64 = 6;
+const CATEGORY_DRONE: u64 = 18;
+const CATEGORY_IMPLANT: u64 = 20;
+const CATEGORY_FIGHTER: u64 = 87;
+const GROUP_BOOSTER: u64 = 303;
+
+// dgmAttributeTypes IDs
+const ATTRIBUTE_LOW_SLOTS: u64 = 12;
+const ATTRIBUTE_MEDIUM_SLOTS: u64 = 13;
+const ATTRIBUTE_HIGH_SLOTS: u64 = 14;
+const ATTRIBUTE_CHARGE_SIZE: u64 = 128;
+const ATTRIBUTE_LAUNCHER_HARDPOINTS: u64 = 101;
+const ATTRIBUTE_TURRET_HARDPOINTS: u64 = 102;
+const ATTRIBUTE_RIG_SLOTS: u64 = 1137;
+const ATTRIBUTE_SUBSYSTEM_SLOTS: u64 = 1367;
+const ATTRIBUTE_RIG_SIZE: u64 = 1547;
+const ATTRIBUTE_SERVICE_SLOTS: u64 = 2056;
+// chargeGroup1 to chargeGroup5, the groups of charges a module can load
+const ATTRIBUTE_CHARGE_GROUPS: [u64; 5] = [604, 605, 606, 609, 610];
+// What a Tech III subsystem adds to its hull
+const ATTRIBUTE_TURRET_HARDPOINT_MODIFIER: u64 = 1368;
+const ATTRIBUTE_LAUNCHER_HARDPOINT_MODIFIER: u64 = 1369;
+const ATTRIBUTE_HIGH_SLOT_MODIFIER: u64 = 1374;
+const ATTRIBUTE_MEDIUM_SLOT_MODIFIER: u64 = 1375;
+const ATTRIBUTE_LOW_SLOT_MODIFIER: u64 = 1376;
+
+// dgmEffects IDs
+const EFFECT_LOW_POWER: u64 = 11;
+const EFFECT_HIGH_POWER: u64 = 12;
+const EFFECT_MEDIUM_POWER: u64 = 13;
+const EFFECT_LAUNCHER_FITTED: u64 = 40;
+const EFFECT_TURRET_FITTED: u64 = 42;
+const EFFECT_RIG_SLOT: u64 = 2663;
+const EFFECT_SUBSYSTEM: u64 = 3772;
+const EFFECT_SERVICE_SLOT: u64 = 6306;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Rack {
+    High,
+    Medium,
+    Low,
+    Rig,
+    Subsystem,
+    Service,
+}
+impl Rack {
+    pub const ALL: [Rack; 6] = [
+        Rack::High,
+        Rack::Medium,
+        Rack::Low,
+        Rack::Rig,
+        Rack::Subsystem,
+        Rack::Service,
+    ];
+
+    // The racks of an EFT fit in the order the game writes them
+    pub(crate) fn from_eft_section(section: usize) -> Option<Rack> {
+        match section {
+            0 => Some(Rack::Low),
+            1 => Some(Rack::Medium),
+            2 => Some(Rack::High),
+            3 => Some(Rack::Rig),
+            _ => None,
+        }
+    }
+
+    // The inverse of from_eft_section. Subsystems and services come straight
+    // after the rigs.
+    pub(crate) fn eft_section(&self) -> usize {
+        match self {
+            Rack::Low => 0,
+            Rack::Medium => 1,
+            Rack::High => 2,
+            Rack::Rig => 3,
+            Rack::Subsystem => 4,
+            Rack::Service => 5,
+        }
+    }
+
+    // What the game writes for an empty slot in this rack
+    pub(crate) fn empty_slot(&self) -> &'static str {
+        match self {
+            Rack::High => "[Empty High slot]",
+            Rack::Medium => "[Empty Med slot]",
+            Rack::Low => "[Empty Low slot]",
+            Rack::Rig => "[Empty Rig slot]",
+            Rack::Subsystem => "[Empty Subsystem slot]",
+            Rack::Service => "[Empty Service slot]",
+        }
+    }
+
+    // The slot column of contract pastes, which parse_fit also fills in
+    pub(crate) fn from_slot_flag(slot_flag: &str) -> Option<Rack> {
+        Rack::ALL
+            .into_iter()
+            .find(|rack| rack.to_string().eq_ignore_ascii_case(slot_flag))
+    }
+
+    // The rack a module goes in, from its slot effect
+    pub fn for_type(db: &TypeDatabase, type_id: u64) -> Option<Rack> {
+        Rack::ALL
+            .into_iter()
+            .find(|rack| db.has_effect(type_id, rack.effect()))
+    }
+
+    fn effect(&self) -> u64 {
+        match self {
+            Rack::High => EFFECT_HIGH_POWER,
+            Rack::Medium => EFFECT_MEDIUM_POWER,
+            Rack::Low => EFFECT_LOW_POWER,
+            Rack::Rig => EFFECT_RIG_SLOT,
+            Rack::Subsystem => EFFECT_SUBSYSTEM,
+            Rack::Service => EFFECT_SERVICE_SLOT,
+        }
+    }
+
+    // The hull attribute with the number of slots, and the subsystem
+    // attribute that adds to it
+    fn slot_attributes(&self) -> (u64, Option<u64>) {
+        match self {
+            Rack::High => (ATTRIBUTE_HIGH_SLOTS, Some(ATTRIBUTE_HIGH_SLOT_MODIFIER)),
+            Rack::Medium => (ATTRIBUTE_MEDIUM_SLOTS, Some(ATTRIBUTE_MEDIUM_SLOT_MODIFIER)),
+            Rack::Low => (ATTRIBUTE_LOW_SLOTS, Some(ATTRIBUTE_LOW_SLOT_MODIFIER)),
+            Rack::Rig => (ATTRIBUTE_RIG_SLOTS, None),
+            Rack::Subsystem => (ATTRIBUTE_SUBSYSTEM_SLOTS, None),
+            Rack::Service => (ATTRIBUTE_SERVICE_SLOTS, None),
+        }
+    }
+}
+impl std::fmt::Display for Rack {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Rack::High => "High Slot",
+            Rack::Medium => "Medium Slot",
+            Rack::Low => "Low Slot",
+            Rack::Rig => "Rig Slot",
+            Rack::Subsystem => "Subsystem Slot",
+            Rack::Service => "Service Slot",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Hardpoint {
+    Turret,
+    Launcher,
+}
+impl Hardpoint {
+    // Whether a module is a turret or launcher, from its hardpoint effect
+    pub(crate) fn for_type(db: &TypeDatabase, type_id: u64) -> Option<Hardpoint> {
+        [Hardpoint::Turret, Hardpoint::Launcher]
+            .into_iter()
+            .find(|hardpoint| db.has_effect(type_id, hardpoint.attributes().0))
+    }
+
+    fn attributes(&self) -> (u64, u64, u64) {
+        match self {
+            Hardpoint::Turret => (
+                EFFECT_TURRET_FITTED,
+                ATTRIBUTE_TURRET_HARDPOINTS,
+                ATTRIBUTE_TURRET_HARDPOINT_MODIFIER,
+            ),
+            Hardpoint::Launcher => (
+                EFFECT_LAUNCHER_FITTED,
+                ATTRIBUTE_LAUNCHER_HARDPOINTS,
+                ATTRIBUTE_LAUNCHER_HARDPOINT_MODIFIER,
+            ),
+        }
+    }
+}
+impl std::fmt::Display for Hardpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Hardpoint::Turret => write!(f, "turret"),
+            Hardpoint::Launcher => write!(f, "launcher"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum FitProblem {
+    // A module in a rack it can't go in, e.g. a shield extender with the low
+    // slot modules
+    WrongRack {
+        type_name: String,
+        rack: Rack,
+        expected: Rack,
+    },
+    RigSizeMismatch {
+        type_name: String,
+        rig_size: u64,
+        hull_rig_size: u64,
+    },
+    RackOverfull {
+        rack: Rack,
+        fitted: u64,
+        slots: u64,
+    },
+    HardpointsExceeded {
+        hardpoint: Hardpoint,
+        fitted: u64,
+        hardpoints: u64,
+    },
+    // A charge the module can't load at all, e.g. a Heavy Missile in a Rapid
+    // Light Missile Launcher
+    IncompatibleCharge {
+        type_name: String,
+        charge: String,
+    },
+    // The right kind of charge in the wrong size, e.g. small crystals in a
+    // large laser
+    ChargeSizeMismatch {
+        type_name: String,
+        charge: String,
+        charge_size: u64,
+        module_charge_size: u64,
+    },
+}
+impl std::fmt::Display for FitProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FitProblem::WrongRack {
+                type_name,
+                rack,
+                expected,
+            } => write!(
+                f,
+                "{} is fitted to a {} but needs a {}",
+                type_name, rack, expected
+            ),
+            FitProblem::RigSizeMismatch {
+                type_name,
+                rig_size,
+                hull_rig_size,
+            } => write!(
+                f,
+                "{} is rig size {} but the hull takes size {}",
+                type_name, rig_size, hull_rig_size
+            ),
+            FitProblem::RackOverfull {
+                rack,
+                fitted,
+                slots,
+            } => write!(f, "{} modules fitted to {} {}s", fitted, slots, rack),
+            FitProblem::HardpointsExceeded {
+                hardpoint,
+                fitted,
+                hardpoints,
+            } => write!(
+                f,
+                "{} {}s fitted to {} {} hardpoints",
+                fitted, hardpoint, hardpoints, hardpoint
+            ),
+            FitProblem::IncompatibleCharge { type_name, charge } => {
+                write!(f, "{} can't be loaded into {}", charge, type_name)
+            }
+            FitProblem::ChargeSizeMismatch {
+                type_name,
+                charge,
+                charge_size,
+                module_charge_size,
+            } => write!(
+                f,
+                "{} is charge size {} but {} takes size {}",
+                charge, charge_size, type_name, module_charge_size
+            ),
+        }
+    }
+}
+
+// How many of a charge one module holds, e.g. for the charge quantity in
+// multibuy output. None if either type is unknown, or the module can't load
+// the charge, see charge_problem.
+pub fn max_charges(db: &TypeDatabase, module_id: u64, charge_id: u64) -> Option<Quantity> {
+    let type_name = db.type_name(module_id)?;
+    let charge = db.type_name(charge_id)?;
+    if charge_problem(db, type_name, charge, module_id, charge_id).is_some() {
+        return None;
+    }
+    let capacity = db.type_info(module_id)?.capacity;
+    let volume = db.type_info(charge_id)?.volume;
+    if capacity <= 0.0 || volume <= 0.0 {
+        return None;
+    }
+    let count = charges_per_load(capacity, volume);
+    (count > 0).then_some(count)
+}
+
+// Whether a module can load a charge, from the module's charge groups and
+// the charge size both of them have. Modules without any charge groups
+// aren't checked, since that's also what missing dgmTypeAttributes looks
+// like.
+pub(crate) fn charge_problem(
+    db: &TypeDatabase,
+    type_name: &str,
+    charge: &str,
+    module_id: u64,
+    charge_id: u64,
+) -> Option<FitProblem> {
+    let charge_groups: Vec<u64> = ATTRIBUTE_CHARGE_GROUPS
+        .iter()
+        .filter_map(|attribute_id| db.attribute_value(module_id, *attribute_id))
+        .map(|group_id| group_id as u64)
+        .collect();
+    let charge_group = db.type_info(charge_id).map(|info| info.group_id);
+    if !charge_groups.is_empty() && !charge_group.is_some_and(|g| charge_groups.contains(&g)) {
+        return Some(FitProblem::IncompatibleCharge {
+            type_name: type_name.to_string(),
+            charge: charge.to_string(),
+        });
+    }
+
+    let charge_size = db.attribute_value(charge_id, ATTRIBUTE_CHARGE_SIZE)?;
+    let module_charge_size = db.attribute_value(module_id, ATTRIBUTE_CHARGE_SIZE)?;
+    (charge_size != module_charge_size).then(|| FitProblem::ChargeSizeMismatch {
+        type_name: type_name.to_string(),
+        charge: charge.to_string(),
+        charge_size: charge_size as u64,
+        module_charge_size: module_charge_size as u64,
+    })
+}
+
+// The type IDs of a fit's hull, and of each of its items and their charges
+// in fit order
+pub(crate) struct ResolvedFit {
+    pub(crate) hull_id: u64,
+    pub(crate) type_ids: Vec<(u64, Option<u64>)>,
+}
+
+// Every item that doesn't resolve is reported at once
+pub(crate) fn resolve_fit(db: &TypeDatabase, fit: &Fit) -> Result<ResolvedFit, String> {
+    let resolve = |name: &str| {
+        db.resolve(name, ResolutionPolicy::default())
+            .ok_or_else(|| fuzzy::lookup_error(db, name))
+    };
+    let hull_id = resolve(&fit.header.hull)?;
+
+    let mut type_ids = Vec::new();
+    let mut errors = Vec::new();
+    for item in fit.items.iter() {
+        let type_id = resolve(&item.type_name);
+        let charge_id = item
+            .charge
+            .as_ref()
+            .map(|charge| resolve(&charge.type_name))
+            .transpose();
+        match (type_id, charge_id) {
+            (Ok(type_id), Ok(charge_id)) => type_ids.push((type_id, charge_id)),
+            (type_id, charge_id) => errors.extend(type_id.err().into_iter().chain(charge_id.err())),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(format!("{:?}", errors));
+    }
+    Ok(ResolvedFit { hull_id, type_ids })
+}
+
+// Checks the modules of a fit against the slots, hardpoints and rig size of
+// the hull in its header, and loaded charges against their modules. Modules
+// are counted in the rack their slot effect says, whatever section of the fit
+// they were in: parse_fit places items by position, and exports that leave out
+// an empty rack shift the sections after it. An empty list means the fit is
+// fine.
+pub fn validate_fit(db: &TypeDatabase, fit: &Fit) -> Result<Vec<FitProblem>, String> {
+    let ResolvedFit { hull_id, type_ids } = resolve_fit(db, fit)?;
+    if db.attribute_value(hull_id, ATTRIBUTE_HIGH_SLOTS).is_none() {
+        return Err(format!(
+            "{} has no slot attributes; is it a ship, and are dgmTypeAttributes loaded?",
+            fit.header.hull
+        ));
+    }
+    let hull_attribute = |attribute_id| db.attribute_value(hull_id, attribute_id).unwrap_or(0.0);
+
+    let mut problems = Vec::new();
+    // (type ID, rack) for every fitted module
+    let mut fitted = Vec::new();
+    for (item, (type_id, charge_id)) in fit.items.iter().zip(type_ids) {
+        if let (Some(charge), Some(charge_id)) = (&item.charge, charge_id) {
+            problems.extend(charge_problem(
+                db,
+                &item.type_name,
+                &charge.type_name,
+                type_id,
+                charge_id,
+            ));
+        }
+        // Drones, charges and cargo have no slot effect, whatever section
+        // they were in
+        let rack = match Rack::for_type(db, type_id) {
+            Some(rack) => rack,
+            None => continue,
+        };
+        let placed = item.slot_flag.as_deref().and_then(Rack::from_slot_flag);
+        if let Some(placed) = placed.filter(|placed| *placed != rack) {
+            problems.push(FitProblem::WrongRack {
+                type_name: item.type_name.clone(),
+                rack: placed,
+                expected: rack,
+            });
+        }
+        fitted.push((type_id, rack));
+
+        if rack == Rack::Rig {
+            let rig_size = db.attribute_value(type_id, ATTRIBUTE_RIG_SIZE);
+            let hull_rig_size = db.attribute_value(hull_id, ATTRIBUTE_RIG_SIZE);
+            if let (Some(rig_size), Some(hull_rig_size)) = (rig_size, hull_rig_size) {
+                if rig_size != hull_rig_size {
+                    problems.push(FitProblem::RigSizeMismatch {
+                        type_name: item.type_name.clone(),
+                        rig_size: rig_size as u64,
+                        hull_rig_size: hull_rig_size as u64,
+                    });
+                }
+            }
+        }
+    }
+
+    // Tech III subsystems add slots and hardpoints to the hull
+    let with_subsystems = |base: f64, modifier: u64| {
+        let added: f64 = fitted
+            .iter()
+            .filter(|(_, rack)| *rack == Rack::Subsystem)
+            .filter_map(|(type_id, _)| db.attribute_value(*type_id, modifier))
+            .sum();
+        (base + added) as u64
+    };
+    for rack in Rack::ALL {
+        let (slots_attribute, modifier) = rack.slot_attributes();
+        let slots = match modifier {
+            Some(modifier) => with_subsystems(hull_attribute(slots_attribute), modifier),
+            None => hull_attribute(slots_attribute) as u64,
+        };
+        let count = fitted.iter().filter(|(_, r)| *r == rack).count() as u64;
+        if count > slots {
+            problems.push(FitProblem::RackOverfull {
+                rack,
+                fitted: count,
+                slots,
+            });
+        }
+    }
+    for hardpoint in [Hardpoint::Turret, Hardpoint::Launcher] {
+        let (effect, hardpoints_attribute, modifier) = hardpoint.attributes();
+        let hardpoints = with_subsystems(hull_attribute(hardpoints_attribute), modifier);
+        let count = fitted
+            .iter()
+            .filter(|(type_id, _)| db.has_effect(*type_id, effect))
+            .count() as u64;
+        if count > hardpoints {
+            problems.push(FitProblem::HardpointsExceeded {
+                hardpoint,
+                fitted: count,
+                hardpoints,
+            });
+        }
+    }
+
+    Ok(problems)
+}
+
+// Where something that isn't fitted to a rack goes, in the order the
+// sections follow the racks
+const SECTIONS: [&str; 5] = [
+    "Drone Bay",
+    "Fighter Bay",
+    "Implant",
+    "Booster",
+    "Cargo Hold",
+];
+
+pub(crate) fn section_for_type(db: &TypeDatabase, type_id: u64) -> &'static str {
+    match db.group_of(type_id) {
+        Some(group) if group.group_id == GROUP_BOOSTER => "Booster",
+        Some(group) => match group.category_id {
+            CATEGORY_DRONE => "Drone Bay",
+            CATEGORY_FIGHTER => "Fighter Bay",
+            CATEGORY_IMPLANT => "Implant",
+            _ => "Cargo Hold",
+        },
+        None => "Cargo Hold",
+    }
+}
+
+// Turns a flat list like a multibuy of a fit back into an EFT fit. The one
+// ship in the list is the hull, modules go in the rack their slot effect says
+// with one line per module, and everything else goes in a section after the
+// racks by its location: drones, fighters, implants, boosters and the cargo
+// hold, which is where charges end up. Modules beyond the hull's slots for
+// their rack go in the cargo hold too; hardpoints and rig size aren't checked,
+// validate_fit does that.
+pub fn infer_fit(db: &TypeDatabase, items: &[ItemWithId]) -> Result<Fit, String> {
+    let is_ship = |item: &&ItemWithId| {
+        db.group_of(item.type_id)
+            .is_some_and(|group| group.category_id == CATEGORY_SHIP)
+    };
+    let ships: Vec<&ItemWithId> = items.iter().filter(is_ship).collect();
+    let hull = match ships.as_slice() {
+        [hull] if hull.quantity == 1 => hull,
+        [] => {
+            return Err("no ship in the list to use as the hull; are invGroups loaded?".to_string())
+        }
+        _ => {
+            let names: Vec<String> = ships.iter().map(|ship| ship.to_string()).collect();
+            return Err(format!("more than one ship in the list: {:?}", names));
+        }
+    };
+
+    if db
+        .attribute_value(hull.type_id, ATTRIBUTE_HIGH_SLOTS)
+        .is_none()
+    {
+        return Err(format!(
+            "{} has no slot attributes; are dgmTypeAttributes loaded?",
+            hull.type_name
+        ));
+    }
+    let hull_attribute = |attribute_id| {
+        db.attribute_value(hull.type_id, attribute_id)
+            .unwrap_or(0.0)
+    };
+    let items: Vec<(&ItemWithId, Option<Rack>)> = items
+        .iter()
+        .filter(|item| !is_ship(item))
+        .map(|item| (item, Rack::for_type(db, item.type_id)))
+        .collect();
+
+    // Tech III subsystems add slots to the hull, so they're fitted first.
+    // (type ID, how many are fitted)
+    let mut subsystems = Vec::new();
+    let mut subsystem_slots = hull_attribute(ATTRIBUTE_SUBSYSTEM_SLOTS) as Quantity;
+    for (item, _) in items
+        .iter()
+        .filter(|(_, rack)| *rack == Some(Rack::Subsystem))
+    {
+        let fitted = item.quantity.min(subsystem_slots);
+        subsystem_slots -= fitted;
+        subsystems.push((item.type_id, fitted));
+    }
+    // Free slots left in each rack, by eft_section
+    let mut free_slots = [0; Rack::ALL.len()];
+    for rack in Rack::ALL {
+        let (slots_attribute, modifier) = rack.slot_attributes();
+        let added: f64 = match modifier {
+            Some(modifier) => subsystems
+                .iter()
+                .filter_map(|(type_id, fitted)| {
+                    db.attribute_value(*type_id, modifier)
+                        .map(|value| value * *fitted as f64)
+                })
+                .sum(),
+            None => 0.0,
+        };
+        free_slots[rack.eft_section()] = (hull_attribute(slots_attribute) + added) as Quantity;
+    }
+
+    // (section, item), sections being the racks then SECTIONS
+    let mut sorted = Vec::new();
+    let in_section = |location: &'static str, type_name: &str, quantity| {
+        let section = SECTIONS.iter().position(|s| *s == location).unwrap();
+        let mut other = DetailedItem::new(type_name.to_string(), quantity);
+        other.location = Some(location.to_string());
+        (Rack::ALL.len() + section, other)
+    };
+    for (item, rack) in items {
+        match rack {
+            Some(rack) => {
+                let free = &mut free_slots[rack.eft_section()];
+                let fitted = item.quantity.min(*free);
+                *free -= fitted;
+                for _ in 0..fitted {
+                    let mut module = DetailedItem::new(item.type_name.clone(), 1);
+                    module.slot_flag = Some(rack.to_string());
+                    sorted.push((rack.eft_section(), module));
+                }
+                if fitted < item.quantity {
+                    sorted.push(in_section(
+                        "Cargo Hold",
+                        &item.type_name,
+                        item.quantity - fitted,
+                    ));
+                }
+            }
+            None => sorted.push(in_section(
+                section_for_type(db, item.type_id),
+                &item.type_name,
+                item.quantity,
+            )),
+        }
+    }
+    // Stable, so each section keeps the order of the list
+    sorted.sort_by_key(|(section, _)| *section);
+
+    Ok(Fit {
+        header: FitHeader {
+            hull: hull.type_name.clone(),
+            name: None,
+        },
+        items: sorted.into_iter().map(|(_, item)| item).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::tests::INV_TYPES;
+    use crate::{parse_detailed_with_options, parse_fit, ParseOptions};
+
+    const INV_GROUPS: &str = "groupID,categoryID,groupName,iconID,useBasePrice,anchored,anchorable,fittableNonSingleton,published
+900,6,Marauder,None,0,0,0,0,1
+62,7,Armor Repair Unit,None,0,0,0,0,1
+53,7,Energy Weapon,None,0,0,0,0,1
+100,18,Combat Drone,None,0,0,0,0,1
+86,8,Frequency Crystal,None,0,0,0,0,1
+";
+    const DGM_TYPE_ATTRIBUTES: &str = "typeID,attributeID,valueInt,valueFloat
+28659,14,7,None
+28659,13,1,None
+28659,12,1,None
+28659,1137,1,None
+28659,102,2,None
+28659,101,0,None
+28659,1547,3,None
+26082,1547,3,None
+31055,1547,2,None
+3057,604,86,None
+3057,128,3,None
+12822,128,3,None
+12818,128,2,None
+1877,604,384,None
+1877,605,395,None
+";
+    const DGM_TYPE_EFFECTS: &str = "typeID,effectID,isDefault
+3540,11,0
+3841,13,0
+3057,12,0
+3057,42,0
+26082,2663,0
+31055,2663,0
+";
+
+    #[test]
+    fn validate() {
+        let mut db = TypeDatabase::from_fuzzwork_csv(INV_TYPES.as_bytes()).unwrap();
+        db.load_fuzzwork_type_attributes_csv(DGM_TYPE_ATTRIBUTES.as_bytes())
+            .unwrap();
+        db.load_fuzzwork_type_effects_csv(DGM_TYPE_EFFECTS.as_bytes())
+            .unwrap();
+
+        let fit = parse_fit(
+            "[Paladin, Bad]
+Large Armor Repairer II
+Large Shield Extender II
+
+Large Shield Extender II
+
+Mega Pulse Laser II
+Mega Pulse Laser II
+Mega Pulse Laser II
+
+Large Trimark Armor Pump I
+Medium Trimark Armor Pump I
+
+
+Hobgoblin II x5",
+        )
+        .unwrap();
+        let problems = validate_fit(&db, &fit).unwrap();
+        assert_eq!(
+            problems,
+            vec![
+                FitProblem::WrongRack {
+                    type_name: String::from("Large Shield Extender II"),
+                    rack: Rack::Low,
+                    expected: Rack::Medium,
+                },
+                FitProblem::RigSizeMismatch {
+                    type_name: String::from("Medium Trimark Armor Pump I"),
+                    rig_size: 2,
+                    hull_rig_size: 3,
+                },
+                FitProblem::RackOverfull {
+                    rack: Rack::Medium,
+                    fitted: 2,
+                    slots: 1,
+                },
+                FitProblem::RackOverfull {
+                    rack: Rack::Rig,
+                    fitted: 2,
+                    slots: 1,
+                },
+                FitProblem::HardpointsExceeded {
+                    hardpoint: Hardpoint::Turret,
+                    fitted: 3,
+                    hardpoints: 2,
+                },
+            ]
+        );
+        assert_eq!(
+            problems[0].to_string(),
+            "Large Shield Extender II is fitted to a Low Slot but needs a Medium Slot"
+        );
+
+        let fit = parse_fit(
+            "[Paladin, Good]\nLarge Armor Repairer II\n\nLarge Shield Extender II\n\nMega Pulse Laser II\n\nLarge Trimark Armor Pump I",
+        )
+        .unwrap();
+        assert_eq!(validate_fit(&db, &fit).unwrap(), vec![]);
+
+        // No rig rack, so parse_fit puts the drones in the rig slots
+        let fit = parse_fit(
+            "[Paladin]\nLarge Armor Repairer II\n\nLarge Shield Extender II\n\nMega Pulse Laser II\n\nHobgoblin II x5",
+        )
+        .unwrap();
+        assert_eq!(fit.items[3].slot_flag.as_deref(), Some("Rig Slot"));
+        assert_eq!(validate_fit(&db, &fit).unwrap(), vec![]);
+
+        assert!(validate_fit(&db, &parse_fit("[Hobgoblin II]").unwrap()).is_err());
+        assert!(validate_fit(&db, &parse_fit("[Paladin]\nNot A Module").unwrap()).is_err());
+    }
+
+    #[test]
+    fn infer() {
+        let mut db = TypeDatabase::from_fuzzwork_csv(INV_TYPES.as_bytes()).unwrap();
+        db.load_fuzzwork_groups_csv(INV_GROUPS.as_bytes()).unwrap();
+        db.load_fuzzwork_type_attributes_csv(DGM_TYPE_ATTRIBUTES.as_bytes())
+            .unwrap();
+        db.load_fuzzwork_type_effects_csv(DGM_TYPE_EFFECTS.as_bytes())
+            .unwrap();
+        let item = |type_id, quantity| ItemWithId::from_type_id(&db, type_id, quantity);
+        let items = vec![
+            item(2488, 5),
+            item(3057, 2),
+            item(12822, 4),
+            item(28659, 1),
+            item(26082, 1),
+            item(3540, 1),
+        ];
+
+        let fit = infer_fit(&db, &items).unwrap();
+        assert_eq!(fit.header.hull, "Paladin");
+        // No medium slot modules, so the rack is kept with an empty slot
+        assert_eq!(
+            fit.to_string(),
+            "[Paladin]
+Large Armor Repairer II
+
+[Empty Med slot]
+
+Mega Pulse Laser II
+Mega Pulse Laser II
+
+Large Trimark Armor Pump I
+
+Hobgoblin II x5
+
+Multifrequency L x4
+"
+        );
+        let reparsed = parse_fit(&fit.to_string()).unwrap();
+        assert_eq!(reparsed.items[1].slot_flag.as_deref(), Some("High Slot"));
+        assert_eq!(validate_fit(&db, &reparsed).unwrap(), vec![]);
+
+        // One low slot, so the rest of the repairers are cargo
+        let fit = infer_fit(&db, &[item(28659, 1), item(3540, 1_000_000_000)]).unwrap();
+        assert_eq!(
+            fit.to_string(),
+            "[Paladin]
+Large Armor Repairer II
+
+[Empty Med slot]
+
+[Empty High slot]
+
+[Empty Rig slot]
+
+Large Armor Repairer II x999999999
+"
+        );
+
+        assert!(infer_fit(&db, &items[..3]).is_err());
+        assert!(infer_fit(&db, &[item(28659, 2)]).is_err());
+    }
+
+    #[test]
+    fn charges() {
+        let mut db = TypeDatabase::from_fuzzwork_csv(INV_TYPES.as_bytes()).unwrap();
+        db.load_fuzzwork_type_attributes_csv(DGM_TYPE_ATTRIBUTES.as_bytes())
+            .unwrap();
+        db.load_fuzzwork_type_effects_csv(DGM_TYPE_EFFECTS.as_bytes())
+            .unwrap();
+
+        let fit = parse_fit(
+            "[Paladin]
+Large Armor Repairer II
+
+Large Shield Extender II
+
+Mega Pulse Laser II, Multifrequency M
+Mega Pulse Laser II, Multifrequency L
+
+[Empty Rig slot]
+
+Rapid Light Missile Launcher II, Scourge Heavy Missile
+Rapid Light Missile Launcher II, Scourge Light Missile",
+        )
+        .unwrap();
+        let problems = validate_fit(&db, &fit).unwrap();
+        assert_eq!(
+            problems,
+            vec![
+                FitProblem::ChargeSizeMismatch {
+                    type_name: String::from("Mega Pulse Laser II"),
+                    charge: String::from("Multifrequency M"),
+                    charge_size: 2,
+                    module_charge_size: 3,
+                },
+                FitProblem::IncompatibleCharge {
+                    type_name: String::from("Rapid Light Missile Launcher II"),
+                    charge: String::from("Scourge Heavy Missile"),
+                },
+            ]
+        );
+        assert_eq!(
+            problems[1].to_string(),
+            "Scourge Heavy Missile can't be loaded into Rapid Light Missile Launcher II"
+        );
+
+        assert_eq!(max_charges(&db, 1877, 210), Some(50));
+        let report = parse_detailed_with_options(
+            &db,
+            "Rapid Light Missile Launcher II, Scourge Heavy Missile",
+            &ParseOptions {
+                fill_charges: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(report.items[0].charge.as_ref().unwrap().quantity, 1);
+        assert_eq!(
+            report.unfilled_charges,
+            vec!["Scourge Heavy Missile can't be loaded into Rapid Light Missile Launcher II"]
+        );
+        // Fits by volume, but isn't a light missile
+        assert_eq!(max_charges(&db, 1877, 209), None);
+        assert_eq!(max_charges(&db, 3057, 12818), None);
+        assert_eq!(max_charges(&db, 3057, 12822), Some(1));
+        assert_eq!(max_charges(&db, 3540, 12822), None);
+        assert_eq!(max_charges(&db, 1877, 1), None);
+    }
+}
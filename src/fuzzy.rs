@@ -0,0 +1,176 @@
+// Fuzzy type name matching for when an exact lookup fails, e.g. because of a
+// typo ("Large Sheild Extender II") or a truncated name ("Large Shield Ext").
+use serde::{Deserialize, Serialize};
+
+use crate::{ResolutionPolicy, TypeDatabase};
+
+// Suggestions scoring at least this are confident enough to be used in place
+// of the name that was typed, see ParseOptions::auto_correct
+pub const AUTO_CORRECT_SCORE: f64 = 0.85;
+// Below this a suggestion isn't worth showing
+const MIN_SUGGESTION_SCORE: f64 = 0.5;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub type_id: u64,
+    pub type_name: String,
+    // Between 0 and 1, where 1 is an exact (case-insensitive) match
+    pub score: f64,
+}
+
+// Ranks every known type name against the given name and returns the best
+// `limit` matches, highest score first
+pub fn suggest(db: &TypeDatabase, name: &str, limit: usize) -> Vec<Suggestion> {
+    rank(
+        name,
+        db.names().map(|(name, _)| {
+            let type_id = db
+                .resolve(name, ResolutionPolicy::default())
+                .expect("known names always resolve");
+            (name, type_id)
+        }),
+        limit,
+    )
+}
+
+// The single best suggestion, if it is clearly better than the runner up.
+// Two near-identical scores mean we can't tell which one was meant.
+pub(crate) fn auto_correct(db: &TypeDatabase, name: &str) -> Option<Suggestion> {
+    let mut suggestions = suggest(db, name, 2).into_iter();
+    let best = suggestions.next()?;
+    if best.score < AUTO_CORRECT_SCORE {
+        return None;
+    }
+    match suggestions.next() {
+        Some(second) if best.score - second.score < 0.05 => None,
+        _ => Some(best),
+    }
+}
+
+// "failed to look up X", with a "did you mean" if there is a decent match
+pub(crate) fn lookup_error(db: &TypeDatabase, name: &str) -> String {
+    match suggest(db, name, 1).first() {
+        Some(s) if s.score >= MIN_SUGGESTION_SCORE => {
+            format!(
+                "failed to look up {}, did you mean '{}'?",
+                name, s.type_name
+            )
+        }
+        _ => format!("failed to look up {}", name),
+    }
+}
+
+fn rank<'a>(
+    name: &str,
+    candidates: impl Iterator<Item = (&'a str, u64)>,
+    limit: usize,
+) -> Vec<Suggestion> {
+    let query = name.to_lowercase();
+    let mut scored: Vec<Suggestion> = candidates
+        .map(|(candidate, type_id)| Suggestion {
+            type_id,
+            type_name: candidate.to_string(),
+            score: score(&query, &candidate.to_lowercase()),
+        })
+        .filter(|s| s.score > 0.0)
+        .collect();
+    // Ties are broken by name so that the order doesn't depend on the phf
+    // map's iteration order
+    scored.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.type_name.cmp(&b.type_name))
+    });
+    scored.truncate(limit);
+    scored
+}
+
+// Both arguments are expected to already be lowercased
+fn score(query: &str, candidate: &str) -> f64 {
+    if query == candidate {
+        return 1.0;
+    }
+    let longest = query.chars().count().max(candidate.chars().count());
+    let edit_similarity = 1.0 - (levenshtein(query, candidate) as f64 / longest as f64);
+
+    // Each query token counts for its best match among the candidate tokens.
+    // A candidate token that starts with the query token is a full match, so
+    // that truncated names still line up.
+    let query_tokens: Vec<&str> = query.split_whitespace().collect();
+    let candidate_tokens: Vec<&str> = candidate.split_whitespace().collect();
+    let matched: f64 = query_tokens
+        .iter()
+        .map(|q| {
+            candidate_tokens
+                .iter()
+                .map(|c| {
+                    if c.starts_with(q) {
+                        return 1.0;
+                    }
+                    let longest = q.chars().count().max(c.chars().count());
+                    1.0 - (levenshtein(q, c) as f64 / longest as f64)
+                })
+                .fold(0.0, f64::max)
+        })
+        .sum();
+    let token_overlap = matched / query_tokens.len().max(candidate_tokens.len()) as f64;
+
+    // Never let a fuzzy match score as high as an exact one
+    (0.5 * edit_similarity + 0.5 * token_overlap).min(0.99)
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == *cb { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAMES: [(&str, u64); 4] = [
+        ("Large Shield Extender I", 3839),
+        ("Large Shield Extender II", 3841),
+        ("Medium Shield Extender II", 3831),
+        ("Paladin", 28659),
+    ];
+
+    #[test]
+    fn distance() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("paladin", "paladin"), 0);
+    }
+
+    #[test]
+    fn typo() {
+        let suggestions = rank("Large Sheild Extender II", NAMES.into_iter(), 2);
+        assert_eq!(suggestions[0].type_name, "Large Shield Extender II");
+        assert!(suggestions[0].score >= AUTO_CORRECT_SCORE);
+    }
+
+    #[test]
+    fn truncated() {
+        let suggestions = rank("Large Shield Ext", NAMES.into_iter(), 3);
+        assert_eq!(suggestions[0].type_name, "Large Shield Extender I");
+        assert_eq!(suggestions[1].type_name, "Large Shield Extender II");
+        assert!(suggestions[0].score < AUTO_CORRECT_SCORE);
+    }
+
+    #[test]
+    fn case_insensitive_exact() {
+        let suggestions = rank("paladin", NAMES.into_iter(), 1);
+        assert_eq!(suggestions[0].type_name, "Paladin");
+        assert_eq!(suggestions[0].score, 1.0);
+    }
+}
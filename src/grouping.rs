@@ -0,0 +1,96 @@
+// Splitting item lists up by group or category, like the grouped view of the
+// in-game inventory
+use serde::{Deserialize, Serialize};
+
+use crate::{ItemWithId, TypeDatabase};
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ItemGroup {
+    // The group or category ID, None for items the database can't place
+    pub id: Option<u64>,
+    pub name: String,
+    pub items: Vec<ItemWithId>,
+}
+
+// Groups sorted by name with the unplaced items last. Items keep their input
+// order within a group.
+pub fn group_by_group(db: &TypeDatabase, items: Vec<ItemWithId>) -> Vec<ItemGroup> {
+    group_by(items, |item| {
+        db.group_of(item.type_id)
+            .map(|group| (group.group_id, group.name.as_str()))
+    })
+}
+
+pub fn group_by_category(db: &TypeDatabase, items: Vec<ItemWithId>) -> Vec<ItemGroup> {
+    group_by(items, |item| {
+        db.category_of(item.type_id)
+            .map(|category| (category.category_id, category.name.as_str()))
+    })
+}
+
+fn group_by<'a>(
+    items: Vec<ItemWithId>,
+    key: impl Fn(&ItemWithId) -> Option<(u64, &'a str)>,
+) -> Vec<ItemGroup> {
+    let mut groups: Vec<ItemGroup> = Vec::new();
+    for item in items {
+        let (id, name) = match key(&item) {
+            Some((id, name)) => (Some(id), name),
+            None => (None, "Unknown"),
+        };
+        match groups.iter_mut().find(|group| group.id == id) {
+            Some(group) => group.items.push(item),
+            None => groups.push(ItemGroup {
+                id,
+                name: name.to_string(),
+                items: vec![item],
+            }),
+        }
+    }
+    groups.sort_by(|a, b| (a.id.is_none(), &a.name).cmp(&(b.id.is_none(), &b.name)));
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::tests::{INV_CATEGORIES, INV_GROUPS, INV_TYPES};
+    use crate::tests::item_with_id;
+
+    #[test]
+    fn groups_and_categories() {
+        let mut db = TypeDatabase::from_fuzzwork_csv(INV_TYPES.as_bytes()).unwrap();
+        db.load_fuzzwork_groups_csv(INV_GROUPS.as_bytes()).unwrap();
+        db.load_fuzzwork_categories_csv(INV_CATEGORIES.as_bytes())
+            .unwrap();
+
+        assert_eq!(db.group_of(24698).unwrap().name, "Combat Battlecruiser");
+        assert_eq!(db.category_of(3841).unwrap().name, "Module");
+        assert_eq!(db.group_of(1), None);
+        let mut drakes: Vec<u64> = db.group_members(419).map(|info| info.type_id).collect();
+        drakes.sort();
+        assert_eq!(drakes, vec![24698, 28661, 28700]);
+        assert_eq!(db.category_members(7).count(), 1);
+        assert_eq!(db.category_types(6).count(), 3);
+
+        let items = vec![
+            item_with_id("Drake", 24698, 1),
+            item_with_id("Large Shield Extender II", 3841, 1),
+            item_with_id("Mystery Box", 1, 1),
+            item_with_id("Drake", 28661, 1),
+        ];
+        let by_group = group_by_group(&db, items.clone());
+        let names: Vec<&str> = by_group.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["Combat Battlecruiser", "Shield Extender", "Unknown"]
+        );
+        assert_eq!(by_group[0].id, Some(419));
+        assert_eq!(by_group[0].items, vec![items[0].clone(), items[3].clone()]);
+        assert_eq!(by_group[2].id, None);
+
+        let by_category = group_by_category(&db, items);
+        let names: Vec<&str> = by_category.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["Module", "Ship", "Unknown"]);
+    }
+}
@@ -0,0 +1,254 @@
+// Expanding a shopping list into the materials needed to build it
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{IndustryActivity, ItemWithId, Quantity, TypeDatabase};
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct IndustryJob {
+    pub blueprint_type_id: u64,
+    // What the job makes, runs times the blueprint's product quantity
+    pub product: ItemWithId,
+    pub runs: Quantity,
+}
+impl std::fmt::Display for IndustryJob {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} x{} ({} runs)",
+            self.product.type_name, self.product.quantity, self.runs
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BillOfMaterials {
+    // Everything that has to be bought, sorted by type ID
+    pub materials: Vec<ItemWithId>,
+    // Every job, parents before the components they need
+    pub jobs: Vec<IndustryJob>,
+    // Products made beyond what was asked for, because blueprints make them
+    // in batches
+    pub surplus: Vec<ItemWithId>,
+    // Requested items that no blueprint makes
+    pub unbuildable: Vec<ItemWithId>,
+}
+
+// Splits each item into jobs of at most runs runs, and works out the
+// materials with the given material efficiency (0 to 10) rounded per job the
+// way the game does. ME only applies to manufacturing, not reactions. With
+// recurse, components that can be built are built too, with the same ME and
+// runs, down to things no blueprint makes like minerals, PI and moon goods.
+pub fn bill_of_materials(
+    db: &TypeDatabase,
+    items: &[ItemWithId],
+    me: u8,
+    runs: Quantity,
+    recurse: bool,
+) -> Result<BillOfMaterials, String> {
+    if me > 10 {
+        return Err(format!("ME must be between 0 and 10, got {}", me));
+    }
+    if runs == 0 {
+        return Err("runs must be at least 1".to_string());
+    }
+
+    let mut demand: HashMap<u64, Quantity> = HashMap::new();
+    let mut unbuildable = Vec::new();
+    for item in items {
+        if db.blueprint_for(item.type_id).is_none() {
+            unbuildable.push(item.clone());
+            continue;
+        }
+        let total = demand.entry(item.type_id).or_default();
+        *total = total
+            .checked_add(item.quantity)
+            .ok_or(format!("{} quantity overflows", item.type_name))?;
+    }
+
+    // Everything that gets built, ordered so that a type comes before the
+    // components it needs. Its demand is then complete by the time it's
+    // built, so shared components are built once.
+    let mut top_level: Vec<u64> = demand.keys().copied().collect();
+    top_level.sort();
+    let build_order = match recurse {
+        true => build_order(db, &top_level)?,
+        false => top_level,
+    };
+
+    let mut materials: BTreeMap<u64, Quantity> = BTreeMap::new();
+    let mut jobs = Vec::new();
+    let mut surplus = Vec::new();
+    for type_id in build_order {
+        let needed = match demand.get(&type_id) {
+            Some(needed) => *needed,
+            None => continue,
+        };
+        let blueprint = db.blueprint_for(type_id).expect("only buildable types");
+        let overflow = || format!("building {} overflows", type_id);
+        let per_run = blueprint.product.quantity.max(1);
+        let total_runs = needed.div_ceil(per_run);
+        let made = total_runs.checked_mul(per_run).ok_or_else(overflow)?;
+        if made > needed {
+            surplus.push(ItemWithId::from_type_id(db, type_id, made - needed));
+        }
+
+        let job_me = match blueprint.activity {
+            IndustryActivity::Manufacturing => me,
+            IndustryActivity::Reaction => 0,
+        };
+        let mut remaining = total_runs;
+        while remaining > 0 {
+            let job_runs = remaining.min(runs);
+            remaining -= job_runs;
+            for material in blueprint.materials.iter() {
+                let quantity = job_material_quantity(material.quantity, job_runs, job_me)
+                    .ok_or_else(overflow)?;
+                let buildable = recurse && db.blueprint_for(material.type_id).is_some();
+                let total = match buildable {
+                    true => demand.entry(material.type_id).or_default(),
+                    false => materials.entry(material.type_id).or_default(),
+                };
+                *total = total.checked_add(quantity).ok_or_else(overflow)?;
+            }
+            jobs.push(IndustryJob {
+                blueprint_type_id: blueprint.blueprint_type_id,
+                product: ItemWithId::from_type_id(db, type_id, job_runs * per_run),
+                runs: job_runs,
+            });
+        }
+    }
+
+    Ok(BillOfMaterials {
+        materials: materials
+            .into_iter()
+            .map(|(type_id, quantity)| ItemWithId::from_type_id(db, type_id, quantity))
+            .collect(),
+        jobs,
+        surplus,
+        unbuildable,
+    })
+}
+
+// The game's per job rounding: the ME reduction is applied to the whole job
+// and rounded up, but a job never takes less than one unit per run
+fn job_material_quantity(base: Quantity, runs: Quantity, me: u8) -> Option<Quantity> {
+    let reduced = base
+        .checked_mul(runs)?
+        .checked_mul(100 - me as Quantity)?
+        .div_ceil(100);
+    Some(reduced.max(runs))
+}
+
+// Every buildable type reachable from the top level ones, each one before
+// the components it needs
+fn build_order(db: &TypeDatabase, top_level: &[u64]) -> Result<Vec<u64>, String> {
+    fn visit(
+        db: &TypeDatabase,
+        type_id: u64,
+        visiting: &mut Vec<u64>,
+        done: &mut Vec<u64>,
+    ) -> Result<(), String> {
+        if done.contains(&type_id) {
+            return Ok(());
+        }
+        if visiting.contains(&type_id) {
+            return Err(format!(
+                "blueprints for {} need their own product",
+                db.type_name(type_id).unwrap_or("Unknown")
+            ));
+        }
+        let blueprint = match db.blueprint_for(type_id) {
+            Some(blueprint) => blueprint,
+            None => return Ok(()),
+        };
+        visiting.push(type_id);
+        for material in blueprint.materials.iter() {
+            visit(db, material.type_id, visiting, done)?;
+        }
+        visiting.pop();
+        done.push(type_id);
+        Ok(())
+    }
+
+    let mut visiting = Vec::new();
+    let mut done = Vec::new();
+    for type_id in top_level {
+        visit(db, *type_id, &mut visiting, &mut done)?;
+    }
+    // done has components before the things made from them
+    done.reverse();
+    Ok(done)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::tests::INV_TYPES;
+    use crate::tests::item_with_id;
+
+    const INDUSTRY_ACTIVITY_PRODUCTS: &str = "typeID,activityID,productTypeID,quantity
+24699,1,24698,1
+3842,1,3841,1
+";
+    const INDUSTRY_ACTIVITY_MATERIALS: &str = "typeID,activityID,materialTypeID,quantity
+24699,1,3841,3
+24699,1,35,1
+3842,1,34,2580
+3842,1,35,1
+";
+
+    #[test]
+    fn me_rounding() {
+        assert_eq!(job_material_quantity(3, 2, 10), Some(6));
+        assert_eq!(job_material_quantity(3, 1, 10), Some(3));
+        assert_eq!(job_material_quantity(1, 10, 10), Some(10));
+        assert_eq!(job_material_quantity(2580, 2, 10), Some(4644));
+        assert_eq!(job_material_quantity(100, 7, 0), Some(700));
+        assert_eq!(job_material_quantity(Quantity::MAX, 2, 0), None);
+    }
+
+    #[test]
+    fn bill_of_materials_jobs() {
+        let mut db = TypeDatabase::from_fuzzwork_csv(INV_TYPES.as_bytes()).unwrap();
+        db.load_fuzzwork_industry_csv(
+            INDUSTRY_ACTIVITY_PRODUCTS.as_bytes(),
+            INDUSTRY_ACTIVITY_MATERIALS.as_bytes(),
+        )
+        .unwrap();
+        let items = vec![
+            item_with_id("Drake", 24698, 5),
+            item_with_id("Tritanium", 34, 10),
+        ];
+
+        // Jobs of 2, 2 and 1 runs
+        let bom = bill_of_materials(&db, &items, 10, 2, false).unwrap();
+        assert_eq!(
+            bom.materials,
+            vec![
+                item_with_id("Pyerite", 35, 5),
+                item_with_id("Large Shield Extender II", 3841, 15)
+            ]
+        );
+        let runs: Vec<Quantity> = bom.jobs.iter().map(|job| job.runs).collect();
+        assert_eq!(runs, vec![2, 2, 1]);
+        assert_eq!(bom.jobs[0].to_string(), "Drake x2 (2 runs)");
+        assert!(bom.surplus.is_empty());
+        assert_eq!(bom.unbuildable, vec![item_with_id("Tritanium", 34, 10)]);
+
+        // The 15 extenders are 7 jobs of 2 and 1 of 1
+        let bom = bill_of_materials(&db, &items, 10, 2, true).unwrap();
+        assert_eq!(
+            bom.materials,
+            vec![
+                item_with_id("Tritanium", 34, 7 * 4644 + 2322),
+                item_with_id("Pyerite", 35, 5 + 15)
+            ]
+        );
+        assert_eq!(bom.jobs.len(), 11);
+        assert_eq!(bom.jobs[3].product.type_id, 3841);
+
+        assert!(bill_of_materials(&db, &items, 11, 1, false).is_err());
+        assert!(bill_of_materials(&db, &items, 0, 0, false).is_err());
+    }
+}
@@ -0,0 +1,311 @@
+// A list of items with type IDs, for working with whole pastes at once
+use serde::{Deserialize, Serialize};
+
+use crate::{ItemWithId, Quantity};
+
+// Orders for ItemList::sort. Sorting is stable, so items that compare equal
+// keep the order they were in.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum ItemOrder {
+    #[default]
+    TypeName,
+    TypeId,
+    // Largest first
+    Quantity,
+}
+
+// Items in the order they were added. Nothing is merged until
+// merge_duplicates is called, so the same module on three lines of a paste
+// stays three entries.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ItemList {
+    items: Vec<ItemWithId>,
+}
+impl ItemList {
+    pub fn new() -> ItemList {
+        ItemList::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn push(&mut self, item: ItemWithId) {
+        self.items.push(item);
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, ItemWithId> {
+        self.items.iter()
+    }
+
+    pub fn as_slice(&self) -> &[ItemWithId] {
+        &self.items
+    }
+
+    // For the functions that sort in place, e.g. sort_by_market_tree
+    pub fn as_mut_slice(&mut self) -> &mut [ItemWithId] {
+        &mut self.items
+    }
+
+    // Adds up the items with the same type ID into the first of them, which
+    // keeps its place in the list
+    pub fn merge_duplicates(&mut self) -> Result<(), String> {
+        let mut merged: Vec<ItemWithId> = Vec::with_capacity(self.items.len());
+        for item in self.items.drain(..) {
+            match merged.iter_mut().find(|m| m.type_id == item.type_id) {
+                Some(existing) => {
+                    existing.quantity = existing
+                        .quantity
+                        .checked_add(item.quantity)
+                        .ok_or(format!("{} quantity overflows", item.type_name))?;
+                }
+                None => merged.push(item),
+            }
+        }
+        self.items = merged;
+        Ok(())
+    }
+
+    pub fn sort(&mut self, order: ItemOrder) {
+        match order {
+            ItemOrder::TypeName => self.items.sort_by(|a, b| a.type_name.cmp(&b.type_name)),
+            ItemOrder::TypeId => self.items.sort_by_key(|item| item.type_id),
+            ItemOrder::Quantity => self
+                .items
+                .sort_by_key(|item| std::cmp::Reverse(item.quantity)),
+        }
+    }
+
+    // The items in type ID order, leaving the list as it is
+    pub fn iter_by_type_id(&self) -> impl Iterator<Item = &ItemWithId> {
+        let mut items: Vec<&ItemWithId> = self.items.iter().collect();
+        items.sort_by_key(|item| item.type_id);
+        items.into_iter()
+    }
+
+    // Multiset arithmetic, e.g. ten copies of a fit minus what's already in
+    // the hangar. Both lists are added up by type ID first, and the result
+    // has one line per type ID, in the order they first appear in self and
+    // then other, without the ones that come to zero.
+    pub fn sum(&self, other: &ItemList) -> Result<ItemList, String> {
+        self.combine(other, |left, right| left.checked_add(right))
+    }
+
+    pub fn saturating_sub(&self, other: &ItemList) -> Result<ItemList, String> {
+        self.combine(other, |left, right| Some(left.saturating_sub(right)))
+    }
+
+    pub fn multiply(&self, n: Quantity) -> Result<ItemList, String> {
+        self.combine(&ItemList::new(), |left, _| left.checked_mul(n))
+    }
+
+    // The smaller quantity of each type, so only types in both lists
+    pub fn intersect(&self, other: &ItemList) -> Result<ItemList, String> {
+        self.combine(other, |left, right| Some(left.min(right)))
+    }
+
+    // The larger quantity of each type
+    pub fn union(&self, other: &ItemList) -> Result<ItemList, String> {
+        self.combine(other, |left, right| Some(left.max(right)))
+    }
+
+    fn combine(
+        &self,
+        other: &ItemList,
+        op: impl Fn(Quantity, Quantity) -> Option<Quantity>,
+    ) -> Result<ItemList, String> {
+        let mut left = self.clone();
+        left.merge_duplicates()?;
+        let mut right = other.clone();
+        right.merge_duplicates()?;
+
+        // (item, total in left, total in right)
+        let mut totals: Vec<(ItemWithId, Quantity, Quantity)> = left
+            .items
+            .into_iter()
+            .map(|item| {
+                let quantity = item.quantity;
+                (item, quantity, 0)
+            })
+            .collect();
+        for item in right.items {
+            match totals
+                .iter_mut()
+                .find(|(t, _, _)| t.type_id == item.type_id)
+            {
+                Some(total) => total.2 = item.quantity,
+                None => {
+                    let quantity = item.quantity;
+                    totals.push((item, 0, quantity));
+                }
+            }
+        }
+
+        let mut combined = ItemList::new();
+        for (item, left, right) in totals {
+            let quantity =
+                op(left, right).ok_or(format!("{} quantity overflows", item.type_name))?;
+            if quantity > 0 {
+                combined.push(ItemWithId { quantity, ..item });
+            }
+        }
+        Ok(combined)
+    }
+
+    // The total over every line with the type ID, saturating at
+    // Quantity::MAX
+    pub fn quantity_of(&self, type_id: u64) -> Quantity {
+        self.items
+            .iter()
+            .filter(|item| item.type_id == type_id)
+            .fold(0, |total: Quantity, item| {
+                total.saturating_add(item.quantity)
+            })
+    }
+}
+// Multibuy format, see format_x
+impl std::fmt::Display for ItemList {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&crate::format_x(self))
+    }
+}
+impl AsRef<[ItemWithId]> for ItemList {
+    fn as_ref(&self) -> &[ItemWithId] {
+        &self.items
+    }
+}
+impl From<Vec<ItemWithId>> for ItemList {
+    fn from(items: Vec<ItemWithId>) -> ItemList {
+        ItemList { items }
+    }
+}
+impl From<ItemList> for Vec<ItemWithId> {
+    fn from(list: ItemList) -> Vec<ItemWithId> {
+        list.items
+    }
+}
+impl FromIterator<ItemWithId> for ItemList {
+    fn from_iter<I: IntoIterator<Item = ItemWithId>>(iter: I) -> ItemList {
+        ItemList {
+            items: iter.into_iter().collect(),
+        }
+    }
+}
+impl Extend<ItemWithId> for ItemList {
+    fn extend<I: IntoIterator<Item = ItemWithId>>(&mut self, iter: I) {
+        self.items.extend(iter);
+    }
+}
+impl IntoIterator for ItemList {
+    type Item = ItemWithId;
+    type IntoIter = std::vec::IntoIter<ItemWithId>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+impl<'a> IntoIterator for &'a ItemList {
+    type Item = &'a ItemWithId;
+    type IntoIter = std::slice::Iter<'a, ItemWithId>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::item_with_id;
+
+    #[test]
+    fn item_list() {
+        let mut list: ItemList = vec![
+            item_with_id("Paladin", 28659, 1),
+            item_with_id("Large Shield Extender II", 3841, 1),
+            item_with_id("Paladin", 28659, 2),
+        ]
+        .into();
+        list.extend([item_with_id("Large Shield Extender II", 3841, 1)]);
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.quantity_of(3841), 2);
+        let ids: Vec<u64> = list.iter_by_type_id().map(|item| item.type_id).collect();
+        assert_eq!(ids, vec![3841, 3841, 28659, 28659]);
+
+        list.merge_duplicates().unwrap();
+        assert_eq!(
+            list.as_slice(),
+            &[
+                item_with_id("Paladin", 28659, 3),
+                item_with_id("Large Shield Extender II", 3841, 2)
+            ]
+        );
+        assert_eq!(
+            list.to_string(),
+            "Paladin x3\nLarge Shield Extender II x2\n"
+        );
+
+        list.push(item_with_id("Tritanium", 34, 2));
+        list.sort(ItemOrder::TypeName);
+        let names: Vec<&str> = list.iter().map(|item| item.type_name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["Large Shield Extender II", "Paladin", "Tritanium"]
+        );
+        list.sort(ItemOrder::Quantity);
+        let quantities: Vec<Quantity> = (&list).into_iter().map(|item| item.quantity).collect();
+        assert_eq!(quantities, vec![3, 2, 2]);
+        // Stable, so the extender stays before the Tritanium
+        assert_eq!(list.as_slice()[1].type_id, 3841);
+        list.sort(ItemOrder::TypeId);
+        let items: Vec<ItemWithId> = list.into();
+        assert_eq!(items[0].type_id, 34);
+
+        let mut overflowing: ItemList = [
+            item_with_id("Tritanium", 34, Quantity::MAX),
+            item_with_id("Tritanium", 34, 1),
+        ]
+        .into_iter()
+        .collect();
+        assert!(overflowing.merge_duplicates().is_err());
+    }
+
+    #[test]
+    fn multiset() {
+        let fit: ItemList = vec![
+            item_with_id("Paladin", 28659, 1),
+            item_with_id("Large Armor Repairer II", 3540, 1),
+            item_with_id("Large Armor Repairer II", 3540, 1),
+        ]
+        .into();
+        let hangar: ItemList = vec![
+            item_with_id("Large Armor Repairer II", 3540, 15),
+            item_with_id("Paladin", 28659, 12),
+            item_with_id("Tritanium", 34, 100),
+        ]
+        .into();
+
+        // Ten fits need ten Paladins and twenty repairers
+        let shortfall = fit.multiply(10).unwrap().saturating_sub(&hangar).unwrap();
+        assert_eq!(crate::format_x(shortfall), "Large Armor Repairer II x5\n");
+        assert_eq!(
+            fit.sum(&hangar).unwrap().to_string(),
+            "Paladin x13\nLarge Armor Repairer II x17\nTritanium x100\n"
+        );
+        assert_eq!(
+            fit.intersect(&hangar).unwrap().to_string(),
+            "Paladin x1\nLarge Armor Repairer II x2\n"
+        );
+        assert_eq!(
+            fit.union(&hangar).unwrap().to_string(),
+            "Paladin x12\nLarge Armor Repairer II x15\nTritanium x100\n"
+        );
+        assert!(fit.multiply(0).unwrap().is_empty());
+        assert!(hangar.multiply(Quantity::MAX).is_err());
+    }
+}
@@ -1,11 +1,52 @@
 use serde::{Deserialize, Serialize};
 
+mod database;
+mod diff;
+mod fitting;
+mod fuzzy;
+mod grouping;
+mod industry;
+mod item_list;
+mod market;
+mod normalize;
+mod reprocessing;
+mod resources;
+mod skills;
+mod snapshot;
+mod volume;
+pub use database::{
+    Attribute, AttributeKey, AttributeType, Blueprint, Category, Group, IndustryActivity,
+    MarketGroup, Material, MetaGroup, ResolutionPolicy, TypeDatabase, TypeInfo, Unit, Variation,
+};
+pub use diff::{diff, DiffEntry, DiffItem, ItemDiff};
+pub use fitting::{infer_fit, max_charges, validate_fit, FitProblem, Hardpoint, Rack};
+pub use fuzzy::{suggest, Suggestion, AUTO_CORRECT_SCORE};
+pub use grouping::{group_by_category, group_by_group, ItemGroup};
+pub use industry::{bill_of_materials, BillOfMaterials, IndustryJob};
+pub use item_list::{ItemList, ItemOrder};
+pub use market::sort_by_market_tree;
+pub use reprocessing::{reprocess, Reprocessed, ReprocessingYields};
+pub use resources::{fit_resources, FitResources, FittingSkills, Resource};
+pub use skills::{required_skills, SkillRequirement};
+pub use volume::{calculate_volume, Packaging, VolumeLine, VolumeReport};
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     // Thanks to https://github.com/harrelchris/eveparse/blob/main/tests/test_parse.py for many of these test cases.
 
     use super::*;
 
+    // For the tests of the other modules, which build lists by hand
+    pub(crate) fn item_with_id(type_name: &str, type_id: u64, quantity: Quantity) -> ItemWithId {
+        ItemWithId {
+            type_name: type_name.to_string(),
+            type_id,
+            quantity,
+            info: None,
+            meta_group: None,
+        }
+    }
+
     #[test]
     fn comma_number() {
         assert_eq!(
@@ -28,6 +69,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn comma_number_grouping() {
+        assert!(parse("Republic Fleet EMP S	3,20,1	Projectile Ammo	Charge	Cargo Hold").is_err());
+        assert!(parse("Republic Fleet EMP S	3200,100	Projectile Ammo	Charge	Cargo Hold").is_err());
+        assert!(parse("Republic Fleet EMP S	3,2000	Projectile Ammo	Charge	Cargo Hold").is_err());
+    }
+
+    #[test]
+    fn quantity_overflow() {
+        assert_eq!(
+            parse("Tritanium	18,446,744,073,709,551,615").unwrap(),
+            vec!(Item {
+                type_name: String::from("Tritanium"),
+                quantity: Quantity::MAX,
+            })
+        );
+        assert!(parse("Tritanium	18,446,744,073,709,551,616").is_err());
+        assert!(parse("Tritanium	999,999,999,999,999,999,999").is_err());
+        assert!(parse("Tritanium x99999999999999999999").is_err());
+        assert!(parse("Large Shield Extender II x0").is_err());
+        assert!(parse("Tritanium	0").is_err());
+        assert_eq!(quantity_delta(5, 3), Some(-2));
+        assert_eq!(quantity_delta(0, Quantity::MAX), None);
+    }
+
     #[test]
     fn intermediate_empty() {
         assert_eq!(
@@ -105,6 +171,7 @@ Golem x3
     }
 
     #[test]
+    #[cfg(feature = "compiled-data")]
     fn name_only() {
         assert_eq!(
             lex("Paladin").unwrap(),
@@ -118,15 +185,27 @@ Golem x3
             }]
         );
         assert_eq!(
-            parse_with_id("Paladin").unwrap(),
+            parse_with_id(TypeDatabase::compiled(), "Paladin").unwrap(),
             vec![ItemWithId {
                 type_name: String::from("Paladin"),
                 type_id: 28659,
-                quantity: 1
+                quantity: 1,
+                info: None,
+                meta_group: None,
             }]
         );
     }
 
+    #[test]
+    #[cfg(feature = "compiled-data")]
+    fn item_list() {
+        let mut list = parse_item_list(TypeDatabase::compiled(), "Paladin\nPaladin* 2").unwrap();
+        assert_eq!(list.len(), 2);
+        list.merge_duplicates().unwrap();
+        assert_eq!(list.quantity_of(28659), 3);
+        assert_eq!(list.to_string(), "Paladin x3\n");
+    }
+
     // It turns out that they are valid input. Multibuy, for example,
     // accepts this format -- it is the format output by jEveAssets
     // when selecting "Copy+ -> EVE MultiBuy". To be able to diff things
@@ -316,6 +395,69 @@ Golem x3
         );
     }
     #[test]
+    fn fit_header() {
+        assert_eq!(
+            parse_fit("[Paladin, Joe's Paladin]\n\nLarge Armor Repairer II\n[Empty Low slot]")
+                .unwrap(),
+            Fit {
+                header: FitHeader {
+                    hull: String::from("Paladin"),
+                    name: Some(String::from("Joe's Paladin")),
+                },
+                items: vec![DetailedItem {
+                    slot_flag: Some(String::from("Low Slot")),
+                    ..DetailedItem::new(String::from("Large Armor Repairer II"), 1)
+                }],
+            }
+        );
+        let fit = parse_fit(
+            "[Paladin]\nLarge Armor Repairer II\n\n\n[Empty Med slot]\n\nMega Pulse Laser II\n\n[Empty Rig slot]\n\n\nHobgoblin II x5",
+        )
+        .unwrap();
+        let slot_flags: Vec<Option<&str>> = fit
+            .items
+            .iter()
+            .map(|item| item.slot_flag.as_deref())
+            .collect();
+        assert_eq!(slot_flags, vec![Some("Low Slot"), Some("High Slot"), None]);
+        assert_eq!(
+            parse_fit("[Paladin, [ABC] Pala]").unwrap().header,
+            FitHeader {
+                hull: String::from("Paladin"),
+                name: Some(String::from("[ABC] Pala")),
+            }
+        );
+        assert_eq!(
+            parse_fit("[Paladin, Joe's Paladin (PvE)]").unwrap().header,
+            FitHeader {
+                hull: String::from("Paladin"),
+                name: Some(String::from("Joe's Paladin (PvE)")),
+            }
+        );
+        assert_eq!(
+            parse_fit("[Paladin, Joe & Co]").unwrap().header.name,
+            Some(String::from("Joe & Co"))
+        );
+        assert!(parse_fit("[Paladin, Joe's Paladin").is_err());
+        assert!(parse_fit("[Paladin, Joe's Paladin] x2").is_err());
+        assert_eq!(
+            parse_fit("[Paladin]").unwrap().header,
+            FitHeader {
+                hull: String::from("Paladin"),
+                name: None,
+            }
+        );
+        assert_eq!(
+            parse("[Paladin]").unwrap(),
+            vec![Item {
+                type_name: String::from("Paladin"),
+                quantity: 1
+            }]
+        );
+        assert!(parse_fit("Paladin x1").is_err());
+        assert!(parse_fit("[Empty Med slot]").is_err());
+    }
+    #[test]
     fn view_contents() {
         assert_eq!(
             lex("Burned Logic Circuit	Salvaged Materials	Cargo Hold	26").unwrap(),
@@ -414,6 +556,52 @@ Golem x3
         );
     }
 
+    #[test]
+    fn contract_columns() {
+        assert_eq!(
+            parse_detailed("Capital Transverse Bulkhead I	2	Rig Armor	Module	Rig Slot").unwrap(),
+            vec![DetailedItem {
+                type_name: String::from("Capital Transverse Bulkhead I"),
+                quantity: 2,
+                group: Some(String::from("Rig Armor")),
+                category: Some(String::from("Module")),
+                location: None,
+                slot_flag: Some(String::from("Rig Slot")),
+                charge: None,
+                state: None,
+            }]
+        );
+        assert_eq!(
+            parse_detailed("Cybernetic Subprocessor - Basic	2	Cyber Learning	Implant	").unwrap(),
+            vec![DetailedItem {
+                type_name: String::from("Cybernetic Subprocessor - Basic"),
+                quantity: 2,
+                group: Some(String::from("Cyber Learning")),
+                category: Some(String::from("Implant")),
+                location: None,
+                slot_flag: None,
+                charge: None,
+                state: None,
+            }]
+        );
+    }
+    #[test]
+    fn view_contents_columns() {
+        assert_eq!(
+            parse_detailed("Burned Logic Circuit	Salvaged Materials	Cargo Hold	26").unwrap(),
+            vec![DetailedItem {
+                type_name: String::from("Burned Logic Circuit"),
+                quantity: 26,
+                group: Some(String::from("Salvaged Materials")),
+                category: None,
+                location: Some(String::from("Cargo Hold")),
+                slot_flag: None,
+                charge: None,
+                state: None,
+            }]
+        );
+    }
+
     #[test]
     fn module_with_charge() {
         assert_eq!(
@@ -445,12 +633,24 @@ Golem x3
                 },
                 Item {
                     type_name: String::from("Shield Harmonizing Charge"),
-                    // TODO: Should this be the max number that can fit in
-                    // this module?
+                    // ParseOptions::fill_charges sets this to however many
+                    // fit in the module instead
                     quantity: 1,
                 }
             ]
         );
+        let mut module = DetailedItem::new(String::from("Shield Command Burst II"), 1);
+        module.charge = Some(Item {
+            type_name: String::from("Shield Harmonizing Charge"),
+            quantity: 1,
+        });
+        assert_eq!(
+            parse_detailed("Shield Command Burst II, Shield Harmonizing Charge").unwrap(),
+            vec![module]
+        );
+        assert_eq!(charges_per_load(50.0, 0.1), 500);
+        assert_eq!(charges_per_load(128.0, 32.0), 4);
+        assert_eq!(charges_per_load(130.0, 32.0), 4);
 
         // TODO: add this as a test? also this
         // Capital Capacitor Booster II, Navy Cap Booster 3200
@@ -459,6 +659,7 @@ Golem x3
     }
 
     #[test]
+    #[cfg(feature = "compiled-data")]
     fn module_with_quotes() {
         assert_eq!(
             lex("'Vehemence' Compact Large EMP Smartbomb x4").unwrap(),
@@ -480,16 +681,51 @@ Golem x3
         );
 
         assert_eq!(
-            parse_with_id("'Vehemence' Compact Large EMP Smartbomb x4").unwrap(),
+            parse_with_id(
+                TypeDatabase::compiled(),
+                "'Vehemence' Compact Large EMP Smartbomb x4"
+            )
+            .unwrap(),
             vec![ItemWithId {
                 type_name: String::from("'Vehemence' Compact Large EMP Smartbomb"),
                 type_id: 9678,
                 quantity: 4,
+                info: None,
+                meta_group: None,
             }]
         );
     }
 
     #[test]
+    fn module_with_curly_quotes() {
+        // Every character normalize_name folds has to get through the lexer
+        assert_eq!(
+            parse(
+                "\u{201C}Dark Blood\u{201D} Heat Sink x2\nCybernetic Subprocessor \u{2212} Basic"
+            )
+            .unwrap(),
+            vec![
+                Item {
+                    type_name: String::from("\u{201C}Dark Blood\u{201D} Heat Sink"),
+                    quantity: 2
+                },
+                Item {
+                    type_name: String::from("Cybernetic Subprocessor \u{2212} Basic"),
+                    quantity: 1
+                },
+            ]
+        );
+        assert_eq!(
+            parse("\"Dark Blood\" Heat Sink x2").unwrap(),
+            vec![Item {
+                type_name: String::from("\"Dark Blood\" Heat Sink"),
+                quantity: 2
+            }]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "compiled-data")]
     fn defunct_offline_syntax() {
         assert_eq!(
             lex("Armor Command Burst II /OFFLINE").unwrap(),
@@ -507,16 +743,237 @@ Golem x3
             ]
         );
         assert_eq!(
-            parse_with_id("Armor Command Burst II /OFFLINE").unwrap(),
+            parse_with_id(TypeDatabase::compiled(), "Armor Command Burst II /OFFLINE").unwrap(),
             vec![ItemWithId {
                 type_name: String::from("Armor Command Burst II"),
                 type_id: 43552,
                 quantity: 1,
+                info: None,
+                meta_group: None,
             }]
         )
     }
 
     #[test]
+    fn module_state() {
+        let items = parse_detailed(
+            "Armor Command Burst II /OFFLINE
+Shield Command Burst II, Shield Harmonizing Charge /OFFLINE
+Large Armor Repairer II",
+        )
+        .unwrap();
+        assert_eq!(items[0].type_name, "Armor Command Burst II");
+        assert_eq!(items[0].state, Some(ModuleState::Offline));
+        assert_eq!(items[1].type_name, "Shield Command Burst II");
+        assert_eq!(items[1].state, Some(ModuleState::Offline));
+        assert_eq!(
+            items[1].charge.as_ref().unwrap().type_name,
+            "Shield Harmonizing Charge"
+        );
+        assert_eq!(items[2].state, None);
+
+        let fit = parse_fit(
+            "[Paladin, Joe's Paladin]
+Armor Command Burst II /OFFLINE
+Shield Command Burst II, Shield Harmonizing Charge
+Navy Cap Booster 3200 x9",
+        )
+        .unwrap();
+        assert_eq!(
+            fit.to_string(),
+            "[Paladin, Joe's Paladin]
+Armor Command Burst II /OFFLINE
+Shield Command Burst II, Shield Harmonizing Charge
+Navy Cap Booster 3200 x9
+"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "compiled-data")]
+    fn lookup_suggestions() {
+        let err =
+            parse_with_id(TypeDatabase::compiled(), "Large Sheild Extender II x2").unwrap_err();
+        assert!(err.contains("did you mean 'Large Shield Extender II'?"));
+        assert_eq!(
+            parse_with_id_with_options(
+                TypeDatabase::compiled(),
+                "Large Sheild Extender II x2",
+                &ParseOptions {
+                    auto_correct: true,
+                    ..Default::default()
+                }
+            )
+            .unwrap()
+            .items,
+            vec![ItemWithId {
+                type_name: String::from("Large Shield Extender II"),
+                type_id: 3841,
+                quantity: 2,
+                info: None,
+                meta_group: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn fill_charges() {
+        let db = TypeDatabase::from_fuzzwork_csv(database::tests::INV_TYPES.as_bytes()).unwrap();
+        let options = ParseOptions {
+            fill_charges: true,
+            ..Default::default()
+        };
+        let report = parse_detailed_with_options(
+            &db,
+            "Rapid Light Missile Launcher II, Scourge Light Missile\nLarge Shield Extender II, Tritanium",
+            &options,
+        )
+        .unwrap();
+        assert_eq!(report.items[0].charge.as_ref().unwrap().quantity, 50);
+        // Left as pasted, rather than failing the whole parse
+        assert_eq!(report.items[1].charge.as_ref().unwrap().quantity, 1);
+        assert_eq!(report.unfilled_charges.len(), 1);
+        assert!(report.unfilled_charges[0].contains("Tritanium"));
+        assert!(parse_detailed_with_options(
+            &db,
+            "Large Shield Extender II, Not A Real Type",
+            &options
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn auto_correct_charges() {
+        let db = TypeDatabase::from_fuzzwork_csv(database::tests::INV_TYPES.as_bytes()).unwrap();
+        let report = parse_with_id_with_options(
+            &db,
+            "Large Sheild Extender II\nRapid Light Missile Launcher II, Scourge Light Misile",
+            &ParseOptions {
+                auto_correct: true,
+                fill_charges: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            report.corrected,
+            vec![
+                (
+                    String::from("Large Sheild Extender II"),
+                    String::from("Large Shield Extender II")
+                ),
+                (
+                    String::from("Scourge Light Misile"),
+                    String::from("Scourge Light Missile")
+                ),
+            ]
+        );
+        assert_eq!(report.items[2].type_name, "Scourge Light Missile");
+        assert_eq!(report.items[2].quantity, 50);
+
+        // Without fill_charges too
+        let report = parse_detailed_with_options(
+            &db,
+            "Large Sheild Extender II",
+            &ParseOptions {
+                auto_correct: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(report.items[0].type_name, "Large Shield Extender II");
+        assert_eq!(report.corrected[0].0, "Large Sheild Extender II");
+    }
+
+    #[test]
+    #[cfg(feature = "compiled-data")]
+    fn normalized_lookup() {
+        assert_eq!(
+            parse_with_id(TypeDatabase::compiled(), "large  shield extender ii x2").unwrap(),
+            vec![ItemWithId {
+                type_name: String::from("Large Shield Extender II"),
+                type_id: 3841,
+                quantity: 2,
+                info: None,
+                meta_group: None,
+            }]
+        );
+        assert_eq!(
+            parse_with_id(
+                TypeDatabase::compiled(),
+                "\u{2018}Vehemence\u{2019} Compact Large EMP Smartbomb x4"
+            )
+            .unwrap(),
+            vec![ItemWithId {
+                type_name: String::from("'Vehemence' Compact Large EMP Smartbomb"),
+                type_id: 9678,
+                quantity: 4,
+                info: None,
+                meta_group: None,
+            }]
+        );
+        assert_eq!(
+            canonical_type_name(TypeDatabase::compiled(), "PALADIN"),
+            Some(String::from("Paladin"))
+        );
+        assert_eq!(
+            lookup_type_name(TypeDatabase::compiled(), String::from("paladin")),
+            Some(28659)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "compiled-data")]
+    fn duplicate_names() {
+        assert!(lookup_type_ids(TypeDatabase::compiled(), "Paladin").contains(&28659));
+        assert!(lookup_type_ids(TypeDatabase::compiled(), "Not A Real Type").is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "compiled-data")]
+    fn attach_type_info() {
+        let db = TypeDatabase::compiled();
+        let options = ParseOptions {
+            attach_type_info: true,
+            ..Default::default()
+        };
+        let report = parse_with_id_with_options(db, "Veldspar x300", &options).unwrap();
+        let info = report.items[0].info.as_ref().unwrap();
+        assert_eq!(info.type_id, 1230);
+        assert_eq!(info.portion_size, 100);
+        assert_eq!(Some(info.clone()), type_info(db, 1230));
+
+        let options = ParseOptions {
+            tag_meta_group: true,
+            ..Default::default()
+        };
+        let report = parse_with_id_with_options(db, "Large Shield Extender II", &options).unwrap();
+        assert_eq!(report.items[0].meta_group.as_ref().unwrap().name, "Tech II");
+
+        let mut item = parse_with_id(db, "Veldspar x300").unwrap().remove(0);
+        assert_eq!(item.info, None);
+        item.attach_type_info(db);
+        assert_eq!(item.info.as_ref(), Some(info));
+    }
+
+    #[test]
+    fn resolution_policy() {
+        // 1 is unpublished, 2 is published but not on the market and 3 is
+        // an unpublished market item
+        let flags = |id| match id {
+            1 => (false, false),
+            2 => (true, false),
+            _ => (false, true),
+        };
+        let ids = [1, 2, 3];
+        assert_eq!(ResolutionPolicy::PreferPublished.pick_by(&ids, flags), 2);
+        assert_eq!(ResolutionPolicy::PreferMarketListed.pick_by(&ids, flags), 3);
+        assert_eq!(ResolutionPolicy::PreferHighestId.pick_by(&ids, flags), 3);
+        assert_eq!(ResolutionPolicy::PreferPublished.pick_by(&[1, 3], flags), 3);
+    }
+
+    #[test]
+    #[cfg(feature = "compiled-data")]
     fn module_with_period() {
         assert_eq!(
             lex("Eifyr and Co. 'Rogue' Navigation NN-602 x1").unwrap(),
@@ -540,11 +997,17 @@ Golem x3
         );
 
         assert_eq!(
-            parse_with_id("'Vehemence' Compact Large EMP Smartbomb x4").unwrap(),
+            parse_with_id(
+                TypeDatabase::compiled(),
+                "'Vehemence' Compact Large EMP Smartbomb x4"
+            )
+            .unwrap(),
             vec![ItemWithId {
                 type_name: String::from("'Vehemence' Compact Large EMP Smartbomb"),
                 type_id: 9678,
                 quantity: 4,
+                info: None,
+                meta_group: None,
             }]
         );
     }
@@ -677,10 +1140,13 @@ fn is_namechar(c: &char) -> bool {
             | '-' // hyphen because of e.g. implant - basic
             | '*' // asterisk because of "Paladin" vs. "Paladin*"
             | '\'' // apostrophe because of e.g. "Joe's Paladin"
+            | '"' // double quote, what curly double quotes are normalised to
             | '/' // forward slash because of weird /OFFLINE syntax
             | '.' // period because of e.g. "Eifyr and Co."
             => true,
-        _ => false,
+        // curly quotes and dashes from pasting through word processors,
+        // cleaned up by the normalised name lookup
+        c => normalize::fold_punctuation(*c).is_some(),
     }
 }
 
@@ -808,10 +1274,23 @@ fn lex(s: &str) -> Result<Vec<Token>, Vec<LexErr>> {
 ////////
 ////////
 
+// Quantities can't be negative. Differences between two quantities, like
+// the ones in a diff, use QuantityDelta instead.
+pub type Quantity = u64;
+pub type QuantityDelta = i64;
+
+// The signed change from one quantity to another, or None if it doesn't fit
+// in a QuantityDelta
+pub fn quantity_delta(from: Quantity, to: Quantity) -> Option<QuantityDelta> {
+    let from = QuantityDelta::try_from(from).ok()?;
+    let to = QuantityDelta::try_from(to).ok()?;
+    to.checked_sub(from)
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Item {
     pub type_name: String,
-    pub quantity: i64,
+    pub quantity: Quantity,
 }
 impl std::fmt::Display for Item {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -819,11 +1298,126 @@ impl std::fmt::Display for Item {
     }
 }
 
+// Pyfa marks modules that aren't simply online with a suffix, e.g.
+// "Armor Command Burst II /OFFLINE". EFT exports only ever use /OFFLINE; the
+// rest follow Pyfa's other module states.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum ModuleState {
+    Offline,
+    Online,
+    Active,
+    Overheated,
+}
+impl ModuleState {
+    fn from_marker(s: &str) -> Option<ModuleState> {
+        match s {
+            "/OFFLINE" => Some(ModuleState::Offline),
+            "/ONLINE" => Some(ModuleState::Online),
+            "/ACTIVE" => Some(ModuleState::Active),
+            "/OVERHEATED" => Some(ModuleState::Overheated),
+            _ => None,
+        }
+    }
+    pub fn marker(&self) -> &'static str {
+        match self {
+            ModuleState::Offline => "/OFFLINE",
+            ModuleState::Online => "/ONLINE",
+            ModuleState::Active => "/ACTIVE",
+            ModuleState::Overheated => "/OVERHEATED",
+        }
+    }
+}
+impl std::fmt::Display for ModuleState {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.marker())
+    }
+}
+
+// The extra columns that come along with some paste formats. Contracts are
+// name-qty-group-category-slot and the contents view is
+// name-group-location-qty; other formats leave these empty.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DetailedItem {
+    pub type_name: String,
+    pub quantity: Quantity,
+    pub group: Option<String>,
+    pub category: Option<String>,
+    pub location: Option<String>,
+    pub slot_flag: Option<String>,
+    // The charge loaded into this module, e.g. "Shield Command Burst II,
+    // Shield Harmonizing Charge"
+    pub charge: Option<Item>,
+    // Only set when the line carries a marker like /OFFLINE
+    pub state: Option<ModuleState>,
+}
+impl DetailedItem {
+    fn new(type_name: String, quantity: Quantity) -> DetailedItem {
+        DetailedItem {
+            type_name,
+            quantity,
+            group: None,
+            category: None,
+            location: None,
+            slot_flag: None,
+            charge: None,
+            state: None,
+        }
+    }
+}
+impl std::fmt::Display for DetailedItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "({}, {}", self.type_name, self.quantity)?;
+        for column in [&self.group, &self.category, &self.location, &self.slot_flag]
+            .into_iter()
+            .flatten()
+        {
+            write!(f, ", {}", column)?;
+        }
+        if let Some(charge) = &self.charge {
+            write!(f, ", loaded with {}", charge)?;
+        }
+        if let Some(state) = &self.state {
+            write!(f, ", {}", state)?;
+        }
+        write!(f, ")")
+    }
+}
+impl From<DetailedItem> for Item {
+    fn from(item: DetailedItem) -> Item {
+        Item {
+            type_name: item.type_name,
+            quantity: item.quantity,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ItemWithId {
     pub type_name: String,
     pub type_id: u64,
-    pub quantity: i64,
+    pub quantity: Quantity,
+    // Filled in by attach_type_info or ParseOptions::attach_type_info
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<TypeInfo>,
+    // Filled in by ParseOptions::tag_meta_group, e.g. Tech II or Faction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta_group: Option<MetaGroup>,
+}
+impl ItemWithId {
+    // For items that come out of the database rather than a paste
+    pub(crate) fn from_type_id(db: &TypeDatabase, type_id: u64, quantity: Quantity) -> ItemWithId {
+        ItemWithId {
+            type_name: db.type_name(type_id).unwrap_or("Unknown").to_string(),
+            type_id,
+            quantity,
+            info: None,
+            meta_group: None,
+        }
+    }
+
+    pub fn attach_type_info(&mut self, db: &TypeDatabase) {
+        self.info = db.type_info(self.type_id).cloned();
+    }
 }
 impl std::fmt::Display for ItemWithId {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -835,11 +1429,95 @@ impl std::fmt::Display for ItemWithId {
     }
 }
 
+// The first line of an EFT fit, e.g. "[Paladin, Joe's Paladin]". The fit name
+// is optional because "[Paladin]" is also a valid header.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FitHeader {
+    pub hull: String,
+    pub name: Option<String>,
+}
+impl FitHeader {
+    fn is_empty_slot(&self) -> bool {
+        self.name.is_none()
+            && [
+                "Empty High slot",
+                "Empty Med slot",
+                "Empty Low slot",
+                "Empty Rig slot",
+                "Empty Subsystem slot",
+            ]
+            .contains(&self.hull.as_str())
+    }
+}
+impl std::fmt::Display for FitHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "[{}, {}]", self.hull, name),
+            None => write!(f, "[{}]", self.hull),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Fit {
+    pub header: FitHeader,
+    // Everything after the header; the hull itself is only in the header
+    pub items: Vec<DetailedItem>,
+}
+// Writes the fit back out in EFT format, keeping loaded charges and module
+// states. Items are written in order with a blank line wherever the rack or
+// location changes. Once any item has a rack, racks with nothing in them up
+// to the rigs get an empty slot line so parse_fit puts everything back in the
+// same rack.
+impl std::fmt::Display for Fit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "{}", self.header)?;
+        let rack_of =
+            |item: &DetailedItem| item.slot_flag.as_deref().and_then(Rack::from_slot_flag);
+        let has_racks = self.items.iter().any(|item| rack_of(item).is_some());
+        let mut previous: Option<(usize, Option<&str>)> = None;
+        for item in &self.items {
+            // Anything without a rack comes after all of them
+            let position = rack_of(item).map_or(Rack::ALL.len(), |rack| rack.eft_section());
+            let section = (position, item.location.as_deref());
+            if previous.is_some_and(|previous| previous != section) {
+                writeln!(f)?;
+            }
+            if has_racks {
+                let first_missing = previous.map_or(0, |(position, _)| position + 1);
+                for missing in first_missing..position.min(Rack::Rig.eft_section() + 1) {
+                    let rack = Rack::from_eft_section(missing).expect("racks up to the rigs");
+                    writeln!(f, "{}\n", rack.empty_slot())?;
+                }
+            }
+            previous = Some(section);
+
+            write!(f, "{}", item.type_name)?;
+            if let Some(charge) = &item.charge {
+                write!(f, ", {}", charge.type_name)?;
+            }
+            if let Some(state) = &item.state {
+                write!(f, " {}", state)?;
+            }
+            if item.quantity != 1 {
+                write!(f, " x{}", item.quantity)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
 // Similarly, the parser is initially taken from dicelang, which is itself
 // heavily inspired by Crafting Intepreters
 struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    // Set by full_name when it skips over a state marker like /OFFLINE
+    state: Option<ModuleState>,
+    // The custom name of a "[Hull, Fit name]" header, which split_fit_name
+    // takes out of the line before it's lexed
+    fit_name: Option<String>,
 }
 impl Parser {
     fn at_end(&self) -> bool {
@@ -898,56 +1576,13 @@ impl Parser {
     /////////
 
     // TODO: synchronize
-    fn item(&mut self) -> Result<Option<Vec<Item>>, String> {
+    fn item(&mut self) -> Result<Option<Vec<DetailedItem>>, String> {
         if self.check(TokenKind::SquareBracketLeft) {
-            self.consume(
-                TokenKind::SquareBracketLeft,
-                "checking a left bracket must consume a left bracket",
-            )?;
-            let full_name = match self.full_name() {
-                Ok(s) => s,
-                Err(e) => {
-                    return Err(format!(
-                        "left bracket must be followed by a name, err: {}",
-                        e
-                    ))
-                }
-            };
-            if full_name == "Empty High slot"
-                || full_name == "Empty Med slot"
-                || full_name == "Empty Low slot"
-            {
+            let header = self.fit_header()?;
+            if header.is_empty_slot() {
                 return Ok(None);
             }
-            self.consume(
-                TokenKind::Comma,
-                "bracketed name must be followed by a comma",
-            )?;
-            if self.check(TokenKind::Space) {
-                self.consume(TokenKind::Space, "checking a space must consume a space")?;
-            }
-
-            // The actual individual item (ship, usually) name can include sub-brackets,
-            // which I don't want to bother supporting parsing of at the moment. Once we've
-            // parsed the comma we know we have the item and no more information is required.
-            // match self.full_name() {
-            //     Err(e) => {
-            //         return Err(format!(
-            //             "bracketed name must have a second name after comma, err: {}",
-            //             e,
-            //         ))
-            //     }
-            //     _ => (),
-            // };
-            // self.consume(
-            //     TokenKind::SquareBracketRight,
-            //     "bracketed names must be terminated by a right bracket",
-            // )?;
-
-            return Ok(Some(vec![Item {
-                type_name: full_name,
-                quantity: 1,
-            }]));
+            return Ok(Some(vec![DetailedItem::new(header.hull, 1)]));
         }
         if self.check(TokenKind::String) || self.check(TokenKind::Number) {
             let full_name = match self.full_name() {
@@ -961,19 +1596,13 @@ impl Parser {
                 }
             };
             if self.at_end() {
-                return Ok(Some(vec![Item {
-                    type_name: full_name,
-                    quantity: 1,
-                }]));
+                return Ok(Some(vec![DetailedItem::new(full_name, 1)]));
             }
 
             // If we consume a name, then get a comma, the next thing should be a charge
             // loaded into that module
             if self.check(TokenKind::Comma) {
-                let first_item = Item {
-                    type_name: full_name,
-                    quantity: 1,
-                };
+                let mut module = DetailedItem::new(full_name, 1);
 
                 self.consume(TokenKind::Comma, "checking comma must consume comma")?;
                 self.consume(
@@ -997,9 +1626,10 @@ impl Parser {
                             ));
                         }
 
-                        return Ok(Some(vec![first_item, items[0].clone()]));
+                        module.charge = Some(Item::from(items[0].clone()));
+                        return Ok(Some(vec![module]));
                     }
-                    Ok(None) => return Ok(Some(vec![first_item])),
+                    Ok(None) => return Ok(Some(vec![module])),
                     Err(e) => {
                         return Err(format!("Failed to match sub-item for loaded charge: {e}"));
                     }
@@ -1015,16 +1645,39 @@ impl Parser {
                         Ok(q) => q,
                         Err(e) => return Err(format!("full name followed by tab then number must match a quantity for the number, err: {}", e)),
                     };
-                    return Ok(Some(vec![Item {
-                        type_name: full_name,
-                        quantity: qty,
-                    }]));
+                    let mut item = DetailedItem::new(full_name, qty);
+
+                    // Contracts follow the quantity with tab-separated group,
+                    // category and slot columns, any of which may be empty.
+                    if self.match_kind(TokenKind::Tab) {
+                        item.group = self.column()?;
+                    }
+                    if self.match_kind(TokenKind::Tab) {
+                        item.category = self.column()?;
+                    }
+                    if self.match_kind(TokenKind::Tab) {
+                        item.slot_flag = self.column()?;
+                    }
+                    return Ok(Some(vec![item]));
                 }
 
-                self.full_name()?;
+                let group = self.full_name()?;
                 self.consume(TokenKind::Tab, "contents view is expected to be name-tab-name-tab-tab-quantity, second tab is missing")?;
-                self.full_name()?;
+                let location = self.full_name()?;
                 self.consume(TokenKind::Tab, "contents view is expected to be name-tab-name-tab-tab-quantity, third tab is missing")?;
+                let qty = match self.quantity() {
+                    Ok(q) => q,
+                    Err(e) => {
+                        return Err(format!(
+                            "contents view must end in a quantity, tokens: {:?} err: {}",
+                            self.tokens, e
+                        ))
+                    }
+                };
+                let mut item = DetailedItem::new(full_name, qty);
+                item.group = Some(group);
+                item.location = Some(location);
+                return Ok(Some(vec![item]));
             }
             let qty = match self.quantity() {
                 Ok(q) => q,
@@ -1036,14 +1689,50 @@ impl Parser {
                     ))
                 }
             };
-            return Ok(Some(vec![Item {
-                type_name: full_name,
-                quantity: qty,
-            }]));
+            return Ok(Some(vec![DetailedItem::new(full_name, qty)]));
         }
 
         return Err(format!("invalid starting token: {:?}", self.peek()));
     }
+    // A bracketed EFT header, e.g. "[Paladin, Joe's Paladin]" or "[Paladin]".
+    // Empty slot markers like "[Empty Low slot]" share the syntax and come
+    // back as a header without a fit name.
+    fn fit_header(&mut self) -> Result<FitHeader, String> {
+        self.consume(
+            TokenKind::SquareBracketLeft,
+            "fit headers must start with a left bracket",
+        )?;
+        let hull = match self.full_name() {
+            Ok(s) => s,
+            Err(e) => {
+                return Err(format!(
+                    "left bracket must be followed by a name, err: {}",
+                    e
+                ))
+            }
+        };
+        self.consume(
+            TokenKind::SquareBracketRight,
+            "bracketed name must be followed by a comma or a right bracket",
+        )?;
+        if !self.at_end() {
+            return Err(format!(
+                "unexpected tokens after bracketed name: {:?}",
+                self.tokens
+            ));
+        }
+        Ok(FitHeader {
+            hull,
+            name: self.fit_name.take(),
+        })
+    }
+    // An optional tab-separated column, e.g. the slot of a contract line
+    fn column(&mut self) -> Result<Option<String>, String> {
+        if self.check(TokenKind::String) || self.check(TokenKind::Number) {
+            return Ok(Some(self.full_name()?));
+        }
+        Ok(None)
+    }
     fn full_name(&mut self) -> Result<String, String> {
         let mut full_string: String = "".to_owned();
         loop {
@@ -1059,9 +1748,10 @@ impl Parser {
                 let tok =
                     self.consume(TokenKind::String, "checking a string must consume a string")?;
 
-                // Some fits have something like this "Armor Command Burst II /OFFLINE" where the
-                // /OFFLINE means nothing for the sake of the item diff.
-                if tok.s == "/OFFLINE" {
+                // Some fits have something like this "Armor Command Burst II /OFFLINE". The
+                // marker isn't part of the name, but keep it around as the module's state.
+                if let Some(state) = ModuleState::from_marker(&tok.s) {
+                    self.state = Some(state);
                     continue;
                 }
                 full_string.push_str(&tok.s);
@@ -1093,7 +1783,7 @@ impl Parser {
         }
         return Ok(cleaned.to_string());
     }
-    fn quantity(&mut self) -> Result<i64, String> {
+    fn quantity(&mut self) -> Result<Quantity, String> {
         if self.check(TokenKind::X) {
             self.consume(TokenKind::X, "checking x must consume x")?;
             if self.check(TokenKind::Space) {
@@ -1103,81 +1793,224 @@ impl Parser {
                 TokenKind::Number,
                 "quantities must have a number after x (optional space in between)",
             )?;
-            let q: i64 = match tok.s.parse() {
+            let q: Quantity = match tok.s.parse() {
                 Ok(u) => u,
-                Err(e) => return Err(format!("parsing {} to i64: {}", tok.s, e)),
+                Err(e) => return Err(format!("parsing {} to a quantity: {}", tok.s, e)),
             };
-            return Ok(q);
+            return nonzero_quantity(q);
         } else {
-            let mut num_sections: Vec<i64> = Vec::new();
             let tok = self.consume(TokenKind::Number, "quantities must be a number")?;
-            let q: i64 = match tok.s.parse() {
+            let mut result: Quantity = match tok.s.parse() {
                 Ok(u) => u,
-                Err(e) => return Err(format!("parsing {} to i64: {}", tok.s, e)),
+                Err(e) => return Err(format!("parsing {} to a quantity: {}", tok.s, e)),
             };
-            num_sections.push(q);
 
+            // Comma-separated numbers like 3,200,189 are digit grouped, so
+            // the first section has at most three digits and every section
+            // after it has exactly three.
+            if self.check(TokenKind::Comma) && tok.s.len() > 3 {
+                return Err(format!(
+                    "digit group {} before a comma is longer than three digits",
+                    tok.s
+                ));
+            }
             while self.check(TokenKind::Comma) {
                 self.consume(TokenKind::Comma, "checking comma must consume comma")?;
                 let tok = self.consume(
                     TokenKind::Number,
                     "numbers followed by comma must also be followed by numbers",
                 )?;
-                let q: i64 = match tok.s.parse() {
+                if tok.s.len() != 3 {
+                    return Err(format!(
+                        "digit group {} after a comma must be three digits",
+                        tok.s
+                    ));
+                }
+                let section: Quantity = match tok.s.parse() {
                     Ok(u) => u,
-                    Err(e) => return Err(format!("parsing {} to i64: {}", tok.s, e)),
+                    Err(e) => return Err(format!("parsing {} to a quantity: {}", tok.s, e)),
+                };
+                result = match result
+                    .checked_mul(1000)
+                    .and_then(|r| r.checked_add(section))
+                {
+                    Some(r) => r,
+                    None => return Err(format!("quantity is too large, max is {}", Quantity::MAX)),
                 };
-                num_sections.push(q);
             }
 
-            // Each num_section is comma-separated, meaning it has to be multiplied
-            // by 10^x, where x is a multiple of 3 (increasing with the number of
-            // sections).
-            let result: i64 = num_sections
-                .iter()
-                .enumerate()
-                .map(|(place, num_section)| {
-                    let base: u32 = 10;
-                    let multiple = base.pow((num_sections.len() as u32 - (place + 1) as u32) * 3);
-                    println!(
-                        "num_section: {}, place: {}, multiple: {}",
-                        num_section, place, multiple
-                    );
-                    num_section * (multiple as i64)
-                })
-                .fold(0, |a, b| a + b);
-
             if self.check(TokenKind::Space) {
                 self.consume(TokenKind::Space, "checking space must consume space")?;
             }
             if self.check(TokenKind::X) {
                 self.consume(TokenKind::X, "checking x must consume x")?;
             }
-            return Ok(result);
+            return nonzero_quantity(result);
+        }
+    }
+}
+
+// The custom fit name is free text, e.g. "[Paladin, Joe's Paladin (PvE)]"
+// or "[Paladin, [ABC] Pala]", so it's cut out of the header as is, up to the
+// bracket that closes the header, rather than lexed. Returns the line with
+// only "[Hull]" left, and the name.
+fn split_fit_name(line: &str) -> Result<(String, Option<String>), String> {
+    let header = match line.strip_prefix('[') {
+        Some(header) => header,
+        None => return Ok((line.to_string(), None)),
+    };
+    let comma = match header.find([',', '[', ']']) {
+        Some(i) if header[i..].starts_with(',') => i,
+        _ => return Ok((line.to_string(), None)),
+    };
+
+    let rest = &header[comma + 1..];
+    let mut depth = 0;
+    let mut end = None;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' if depth == 0 => {
+                end = Some(i);
+                break;
+            }
+            ']' => depth -= 1,
+            _ => (),
         }
     }
+    let end = match end {
+        Some(end) => end,
+        None => return Err(format!("fit header is missing its closing bracket: {line}")),
+    };
+    if !rest[end + 1..].trim().is_empty() {
+        return Err(format!("unexpected text after fit header: {line}"));
+    }
+
+    let name = rest[..end].trim();
+    Ok((
+        format!("[{}]", &header[..comma]),
+        (!name.is_empty()).then(|| name.to_string()),
+    ))
 }
 
+fn line_parser(line: &str) -> Result<Parser, String> {
+    let (line, fit_name) = split_fit_name(line)?;
+    let tokens = match lex(&line) {
+        Ok(tokens) => tokens,
+        Err(errs) => return Err(errs.iter().fold(String::new(), |acc, e| acc + e)),
+    };
+    Ok(Parser {
+        tokens,
+        current: 0,
+        state: None,
+        fit_name,
+    })
+}
+
+// A line for none of an item is almost certainly a mistake, and would only
+// turn into empty entries in item lists and diffs
+fn nonzero_quantity(quantity: Quantity) -> Result<Quantity, String> {
+    match quantity {
+        0 => Err("quantities must be at least 1".to_string()),
+        quantity => Ok(quantity),
+    }
+}
+
+fn parse_line(line: &str) -> Result<Option<Vec<DetailedItem>>, String> {
+    let mut p = line_parser(line)?;
+    let mut items = p.item()?;
+    // The marker comes at the very end of the line, after any loaded charge,
+    // but it always belongs to the module.
+    if let Some(module) = items.as_mut().and_then(|items| items.first_mut()) {
+        module.state = p.state;
+    }
+    Ok(items)
+}
+
+// Loaded charges are listed as their own item directly after the module
 pub fn parse(s: &str) -> Result<Vec<Item>, String> {
-    let results: Vec<Result<Vec<Item>, String>> = s
+    let items = parse_detailed(s)?;
+    Ok(flatten_charges(items))
+}
+
+fn flatten_charges(items: Vec<DetailedItem>) -> Vec<Item> {
+    let mut flat = Vec::new();
+    for mut item in items {
+        let charge = item.charge.take();
+        flat.push(Item::from(item));
+        flat.extend(charge);
+    }
+    flat
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ParseOptions {
+    // Set the quantity of each loaded charge to the number of charges that
+    // fit in its module (see max_charges) instead of 1
+    pub fill_charges: bool,
+    // When a type name can't be found, use the closest known name instead of
+    // failing, as long as the match is confident (see fuzzy::AUTO_CORRECT_SCORE)
+    pub auto_correct: bool,
+    // Which ID to use for names shared by several types
+    pub resolution: ResolutionPolicy,
+    // Fill in ItemWithId::info for every resolved item
+    pub attach_type_info: bool,
+    // Fill in ItemWithId::meta_group for every resolved item
+    pub tag_meta_group: bool,
+}
+
+// Like parse, but keeps the extra columns of contract and contents view
+// pastes.
+pub fn parse_detailed(s: &str) -> Result<Vec<DetailedItem>, String> {
+    parse_lines(s)
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DetailedParseReport {
+    pub items: Vec<DetailedItem>,
+    // (name as pasted, name it was auto-corrected to) for every distinct
+    // name ParseOptions::auto_correct changed, in the order they first appear
+    pub corrected: Vec<(String, String)>,
+    // Why each loaded charge that ParseOptions::fill_charges couldn't fill
+    // was left at its pasted quantity, e.g. a charge the module can't hold
+    pub unfilled_charges: Vec<String>,
+}
+
+// Options like fill_charges need type data, so this one takes a database
+pub fn parse_detailed_with_options(
+    db: &TypeDatabase,
+    s: &str,
+    options: &ParseOptions,
+) -> Result<DetailedParseReport, String> {
+    let mut items = parse_lines(s)?;
+    // Charges are corrected along with their modules, before they're filled
+    let corrected = match options.auto_correct {
+        true => auto_correct_names(db, &mut items)?,
+        false => Vec::new(),
+    };
+    let mut unfilled_charges = Vec::new();
+    if options.fill_charges {
+        for item in items.iter_mut() {
+            unfilled_charges.extend(fill_charge(db, item, options.resolution)?);
+        }
+    }
+    Ok(DetailedParseReport {
+        items,
+        corrected,
+        unfilled_charges,
+    })
+}
+
+fn parse_lines(s: &str) -> Result<Vec<DetailedItem>, String> {
+    let results: Vec<Result<Vec<DetailedItem>, String>> = s
         .lines()
         .map(|line| line.trim())
         .filter(|line| line.len() > 0)
         .enumerate()
-        .filter_map(|(i, line)| {
-            let tokens = match lex(line.trim()) {
-                Ok(tokens) => tokens,
-                Err(errs) => {
-                    let s = errs.iter().fold(String::new(), |acc, e| acc + e);
-                    return Some(Err(format!("line {i}: {s}")));
-                }
-            };
-            let mut p = Parser { tokens, current: 0 };
-            match p.item() {
-                Ok(Some(item)) => Some(Ok(item)),
-                Ok(None) => None,
-                Err(e) => Some(Err(e)),
-            }
+        .filter_map(|(i, line)| match parse_line(line) {
+            Ok(Some(item)) => Some(Ok(item)),
+            Ok(None) => None,
+            Err(e) => Some(Err(format!("line {i}: {e}"))),
         })
         .collect();
 
@@ -1197,39 +2030,269 @@ pub fn parse(s: &str) -> Result<Vec<Item>, String> {
     return Ok(items);
 }
 
+// Parses a single EFT fit. The first non-empty line has to be the
+// "[Hull, Fit name]" header.
+pub fn parse_fit(s: &str) -> Result<Fit, String> {
+    let mut lines = s
+        .lines()
+        .map(|line| line.trim())
+        .skip_while(|line| line.is_empty());
+    let first = match lines.next() {
+        Some(line) => line,
+        None => return Err("fit is empty".to_string()),
+    };
+    let header = match line_parser(first).and_then(|mut p| p.fit_header()) {
+        Ok(header) => header,
+        Err(e) => return Err(format!("fits must start with an EFT header, err: {e}")),
+    };
+    if header.is_empty_slot() {
+        return Err(format!("fits must start with an EFT header, got {header}"));
+    }
+
+    // The game writes the racks in a fixed order with a blank line after each
+    // one, using [Empty Low slot] and the like for empty slots, so the section
+    // a module is in says which rack it's fitted to. Sections after the rigs
+    // can be subsystems, drones or cargo so they don't get a slot flag.
+    let mut items = Vec::new();
+    let mut errors = Vec::new();
+    let mut section = 0;
+    let mut in_section = false;
+    for (i, line) in lines.enumerate() {
+        if line.is_empty() {
+            if in_section {
+                section += 1;
+                in_section = false;
+            }
+            continue;
+        }
+        in_section = true;
+        match parse_line(line) {
+            Ok(Some(is)) => items.extend(is.into_iter().map(|mut item| {
+                item.slot_flag = Rack::from_eft_section(section).map(|rack| rack.to_string());
+                item
+            })),
+            Ok(None) => (),
+            Err(e) => errors.push(format!("line {}: {e}", i + 1)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(format!("{:?}", errors));
+    }
+
+    Ok(Fit { header, items })
+}
+
+// (group, volume, capacity, mass, portion size, base price, published, market
+// group) for CODE_TO_INFO, see build.rs
+#[cfg(feature = "compiled-data")]
+type CompiledTypeInfo = (u64, f64, f64, f64, u64, Option<f64>, bool, Option<u64>);
+
+// (attribute ID, name, display name, unit ID, published) for ATTRIBUTE_TYPES
+#[cfg(feature = "compiled-data")]
+type CompiledAttributeType = (u64, &'static str, Option<&'static str>, Option<u64>, bool);
+
+#[cfg(feature = "compiled-data")]
 include!(concat!(env!("OUT_DIR"), "/codegen.rs"));
 
-pub fn parse_with_id(s: &str) -> Result<Vec<ItemWithId>, String> {
-    let items = parse(s)?;
-    items
-        .iter()
-        .map(|item| {
-            let id = ITEM_TO_CODE
-                .get(&item.type_name)
-                .ok_or(format!("failed to look up {}", item.type_name))?;
-            return Ok(ItemWithId {
-                type_name: item.type_name.clone(),
-                quantity: item.quantity,
-                type_id: *id,
-            });
-        })
-        .collect()
+fn charges_per_load(capacity: f64, volume: f64) -> Quantity {
+    // The small epsilon keeps e.g. 50 / 0.1 from flooring to 499
+    ((capacity / volume) + 1e-6).floor() as Quantity
 }
 
-pub fn lookup_id(id: u64) -> Option<String> {
-    match CODE_TO_ITEM.get(&id).copied() {
-        Some(s) => Some(s.to_string()),
-        None => None,
+// Unknown names are an error, but a charge that doesn't fit in the module
+// is only reported, since the rest of the paste is still fine
+fn fill_charge(
+    db: &TypeDatabase,
+    module: &mut DetailedItem,
+    policy: ResolutionPolicy,
+) -> Result<Option<String>, String> {
+    let charge = match module.charge.as_mut() {
+        Some(charge) => charge,
+        None => return Ok(None),
+    };
+    let module_id = db
+        .resolve(&module.type_name, policy)
+        .ok_or_else(|| fuzzy::lookup_error(db, &module.type_name))?;
+    let charge_id = db
+        .resolve(&charge.type_name, policy)
+        .ok_or_else(|| fuzzy::lookup_error(db, &charge.type_name))?;
+    if let Some(count) = max_charges(db, module_id, charge_id) {
+        charge.quantity = count;
+        return Ok(None);
     }
+    let problem = fitting::charge_problem(
+        db,
+        &module.type_name,
+        &charge.type_name,
+        module_id,
+        charge_id,
+    );
+    Ok(Some(match problem {
+        Some(problem) => problem.to_string(),
+        None => format!(
+            "can't fit {} (volume {}) in {} (capacity {})",
+            charge.type_name,
+            db.volume(charge_id),
+            module.type_name,
+            db.capacity(module_id)
+        ),
+    }))
+}
+
+// A name that matched more than one type ID, and the one the policy picked
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AmbiguousName {
+    pub type_name: String,
+    pub type_ids: Vec<u64>,
+    pub chosen: u64,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ParseReport {
+    pub items: Vec<ItemWithId>,
+    // One entry per distinct name, in the order they first appear
+    pub ambiguous: Vec<AmbiguousName>,
+    // See DetailedParseReport
+    pub corrected: Vec<(String, String)>,
+    pub unfilled_charges: Vec<String>,
+}
+
+// Renames the modules and charges that aren't known types to their
+// auto-correction, and returns what was renamed. Names with no confident
+// correction are an error.
+fn auto_correct_names(
+    db: &TypeDatabase,
+    items: &mut [DetailedItem],
+) -> Result<Vec<(String, String)>, String> {
+    let mut corrected: Vec<(String, String)> = Vec::new();
+    let mut errors = Vec::new();
+    let names = items.iter_mut().flat_map(|item| {
+        std::iter::once(&mut item.type_name)
+            .chain(item.charge.as_mut().map(|charge| &mut charge.type_name))
+    });
+    for name in names {
+        if db.canonical_name(name).is_some() {
+            continue;
+        }
+        let correction = fuzzy::auto_correct(db, name)
+            .and_then(|suggestion| db.canonical_name(&suggestion.type_name));
+        match correction {
+            Some(correction) => {
+                if !corrected.iter().any(|(input, _)| input == name) {
+                    corrected.push((name.clone(), correction.to_string()));
+                }
+                *name = correction.to_string();
+            }
+            None => errors.push(fuzzy::lookup_error(db, name)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(format!("{:?}", errors));
+    }
+    Ok(corrected)
+}
+
+pub fn parse_with_id(db: &TypeDatabase, s: &str) -> Result<Vec<ItemWithId>, String> {
+    Ok(parse_with_id_with_options(db, s, &ParseOptions::default())?.items)
+}
+
+pub fn parse_with_id_with_options(
+    db: &TypeDatabase,
+    s: &str,
+    options: &ParseOptions,
+) -> Result<ParseReport, String> {
+    let DetailedParseReport {
+        items,
+        corrected,
+        unfilled_charges,
+    } = parse_detailed_with_options(db, s, options)?;
+
+    let mut resolved = Vec::new();
+    let mut ambiguous: Vec<AmbiguousName> = Vec::new();
+    let mut errors = Vec::new();
+    for item in flatten_charges(items) {
+        let name = match db.canonical_name(&item.type_name) {
+            Some(name) => name,
+            None => {
+                errors.push(fuzzy::lookup_error(db, &item.type_name));
+                continue;
+            }
+        };
+
+        let type_id = db
+            .resolve(name, options.resolution)
+            .expect("canonical names always resolve");
+        let type_ids = db.type_ids(name);
+        if type_ids.len() > 1 && !ambiguous.iter().any(|a| a.type_name == name) {
+            ambiguous.push(AmbiguousName {
+                type_name: name.to_string(),
+                type_ids: type_ids.to_vec(),
+                chosen: type_id,
+            });
+        }
+        resolved.push(ItemWithId {
+            type_name: name.to_string(),
+            quantity: item.quantity,
+            type_id,
+            info: match options.attach_type_info {
+                true => db.type_info(type_id).cloned(),
+                false => None,
+            },
+            meta_group: match options.tag_meta_group {
+                true => db.meta_group_of(type_id).cloned(),
+                false => None,
+            },
+        });
+    }
+
+    if !errors.is_empty() {
+        return Err(format!("{:?}", errors));
+    }
+    Ok(ParseReport {
+        items: resolved,
+        ambiguous,
+        corrected,
+        unfilled_charges,
+    })
+}
+
+// Like parse_with_id, as an ItemList. Duplicates are kept; call
+// merge_duplicates to add them up.
+pub fn parse_item_list(db: &TypeDatabase, s: &str) -> Result<ItemList, String> {
+    Ok(parse_with_id(db, s)?.into())
+}
+
+pub fn lookup_id(db: &TypeDatabase, id: u64) -> Option<String> {
+    db.type_name(id).map(|s| s.to_string())
+}
+
+pub fn type_info(db: &TypeDatabase, id: u64) -> Option<TypeInfo> {
+    db.type_info(id).cloned()
+}
+
+// Hand typed names like "large shield extender ii" resolve too. Names with
+// more than one type ID resolve with the default ResolutionPolicy.
+pub fn lookup_type_name(db: &TypeDatabase, type_name: String) -> Option<u64> {
+    db.resolve(&type_name, ResolutionPolicy::default())
+}
+
+// Every type ID with the given name, lowest first
+pub fn lookup_type_ids(db: &TypeDatabase, type_name: &str) -> Vec<u64> {
+    db.type_ids(type_name).to_vec()
 }
 
-pub fn lookup_type_name(type_name: String) -> Option<u64> {
-    ITEM_TO_CODE.get(&type_name).copied()
+// The SDE spelling of a type name, e.g. "Large Shield Extender II" for
+// "large shield extender ii"
+pub fn canonical_type_name(db: &TypeDatabase, type_name: &str) -> Option<String> {
+    db.canonical_name(type_name).map(|name| name.to_string())
 }
 
-pub fn format_x(items: Vec<ItemWithId>) -> String {
+// Multibuy format, one "Name xN" line per item. Takes an ItemList, or the
+// missing items of a diff, too.
+pub fn format_x<T: DiffItem>(items: impl AsRef<[T]>) -> String {
     items
+        .as_ref()
         .iter()
-        .map(|item| format!("{} x{}\n", item.type_name, item.quantity))
+        .map(|item| format!("{} x{}\n", item.type_name(), item.quantity()))
         .fold("".to_string(), |cur, next| cur + &next)
 }
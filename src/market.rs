@@ -0,0 +1,57 @@
+// Ordering item lists the way the in-game market browser lists them
+use crate::{ItemWithId, TypeDatabase};
+
+// Sorts by market group path and then type name, with each level of the tree
+// in name order like the market browser. Items that aren't on the market go
+// last, by name. The sort is stable, so repeated lines keep their order.
+pub fn sort_by_market_tree(db: &TypeDatabase, items: &mut [ItemWithId]) {
+    items.sort_by_cached_key(|item| {
+        let path: Vec<String> = db
+            .market_path(item.type_id)
+            .iter()
+            .map(|market_group| market_group.name.clone())
+            .collect();
+        (path.is_empty(), path, item.type_name.clone())
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::tests::{INV_MARKET_GROUPS, INV_TYPES};
+    use crate::tests::item_with_id;
+
+    #[test]
+    fn market_tree() {
+        let mut db = TypeDatabase::from_fuzzwork_csv(INV_TYPES.as_bytes()).unwrap();
+        db.load_fuzzwork_market_groups_csv(INV_MARKET_GROUPS.as_bytes())
+            .unwrap();
+
+        let path: Vec<&str> = db
+            .market_path(3841)
+            .iter()
+            .map(|market_group| market_group.name.as_str())
+            .collect();
+        assert_eq!(path, vec!["Ship Equipment", "Shield", "Shield Extenders"]);
+        assert!(db.market_path(28661).is_empty());
+        let roots: Vec<u64> = db
+            .market_group_children(None)
+            .iter()
+            .map(|market_group| market_group.market_group_id)
+            .collect();
+        assert_eq!(roots, vec![9, 4]);
+        assert_eq!(
+            db.market_group_children(Some(551))[0].name,
+            "Shield Extenders"
+        );
+
+        let mut items = vec![
+            item_with_id("Drake", 28661, 1),
+            item_with_id("Drake", 24698, 1),
+            item_with_id("Large Shield Extender II", 3841, 1),
+        ];
+        sort_by_market_tree(&db, &mut items);
+        let ids: Vec<u64> = items.iter().map(|item| item.type_id).collect();
+        assert_eq!(ids, vec![3841, 24698, 28661]);
+    }
+}
@@ -0,0 +1,55 @@
+// Type name normalisation shared with build.rs, which uses it to build the
+// NORMALIZED_TO_ITEM index. Hand typed or oddly pasted names like
+// "large  shield extender ii" or "‘Vehemence’ Compact Large EMP Smartbomb"
+// normalise to the same key as the SDE name.
+
+// The ASCII character a quote or dash variant folds to. The lexer accepts
+// exactly these in names, so anything it reads can be looked up.
+pub fn fold_punctuation(c: char) -> Option<char> {
+    match c {
+        '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' | '`' | '\u{B4}' => {
+            Some('\'')
+        }
+        '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => Some('"'),
+        '\u{2010}'..='\u{2015}' | '\u{2212}' => Some('-'),
+        _ => None,
+    }
+}
+
+// Folds case, quote styles, dash variants and runs of whitespace
+pub fn normalize_name(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    for word in name.split_whitespace() {
+        if !normalized.is_empty() {
+            normalized.push(' ');
+        }
+        for c in word.chars() {
+            match fold_punctuation(c) {
+                Some(folded) => normalized.push(folded),
+                None => normalized.extend(c.to_lowercase()),
+            }
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds() {
+        assert_eq!(
+            normalize_name("  Large   Shield Extender II "),
+            "large shield extender ii"
+        );
+        assert_eq!(
+            normalize_name("\u{2018}Vehemence\u{2019} Compact Large EMP Smartbomb"),
+            "'vehemence' compact large emp smartbomb"
+        );
+        assert_eq!(
+            normalize_name("Cybernetic Subprocessor \u{2013} Basic"),
+            "cybernetic subprocessor - basic"
+        );
+    }
+}
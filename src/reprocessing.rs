@@ -0,0 +1,181 @@
+// Working out what a list of items reprocesses into
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{ItemWithId, Quantity, TypeDatabase};
+
+// invCategories IDs that pick the yield for a type
+const CATEGORY_MODULE: u64 = 7;
+const CATEGORY_ASTEROID: u64 = 25;
+
+// The fraction of the materials that come out of reprocessing, from 0 to 1.
+// Station or structure, rigs, implants and skills all feed into these, so
+// they're left to the caller. Ore covers everything in the Asteroid category
+// (including ice and moon ore), modules the Module category and scrapmetal
+// everything else, e.g. charges and ships.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct ReprocessingYields {
+    pub ore: f64,
+    pub modules: f64,
+    pub scrapmetal: f64,
+}
+impl ReprocessingYields {
+    pub fn uniform(efficiency: f64) -> ReprocessingYields {
+        ReprocessingYields {
+            ore: efficiency,
+            modules: efficiency,
+            scrapmetal: efficiency,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (name, efficiency) in [
+            ("ore", self.ore),
+            ("modules", self.modules),
+            ("scrapmetal", self.scrapmetal),
+        ] {
+            if !(0.0..=1.0).contains(&efficiency) {
+                return Err(format!(
+                    "{} yield must be between 0 and 1, got {}",
+                    name, efficiency
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn for_type(&self, db: &TypeDatabase, type_id: u64) -> f64 {
+        match db.group_of(type_id).map(|group| group.category_id) {
+            Some(CATEGORY_ASTEROID) => self.ore,
+            Some(CATEGORY_MODULE) => self.modules,
+            _ => self.scrapmetal,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Reprocessed {
+    // Every material, added up across the input and sorted by type ID
+    pub materials: Vec<ItemWithId>,
+    // What didn't get reprocessed: the units left over after the last full
+    // portion, and items that don't reprocess into anything
+    pub unprocessed: Vec<ItemWithId>,
+}
+
+pub fn reprocess(
+    db: &TypeDatabase,
+    items: &[ItemWithId],
+    yields: ReprocessingYields,
+) -> Result<Reprocessed, String> {
+    yields.validate()?;
+
+    let mut materials: BTreeMap<u64, Quantity> = BTreeMap::new();
+    let mut unprocessed = Vec::new();
+    for item in items {
+        let type_materials = db.type_materials(item.type_id);
+        let portion_size = db
+            .type_info(item.type_id)
+            .map_or(1, |info| info.portion_size.max(1));
+        if type_materials.is_empty() {
+            unprocessed.push(item.clone());
+            continue;
+        }
+
+        let portions = item.quantity / portion_size;
+        let left_over = item.quantity % portion_size;
+        if left_over > 0 {
+            unprocessed.push(ItemWithId {
+                quantity: left_over,
+                ..item.clone()
+            });
+        }
+        if portions == 0 {
+            continue;
+        }
+
+        let efficiency = yields.for_type(db, item.type_id);
+        for material in type_materials {
+            let base = material.quantity.checked_mul(portions).ok_or(format!(
+                "reprocessing {} x{} overflows",
+                item.type_name, item.quantity
+            ))?;
+            let quantity = (base as f64 * efficiency).floor() as Quantity;
+            let total = materials.entry(material.type_id).or_default();
+            *total = total.checked_add(quantity).ok_or(format!(
+                "reprocessing {} x{} overflows",
+                item.type_name, item.quantity
+            ))?;
+        }
+    }
+
+    let materials = materials
+        .into_iter()
+        .filter(|(_, quantity)| *quantity > 0)
+        .map(|(type_id, quantity)| ItemWithId::from_type_id(db, type_id, quantity))
+        .collect();
+    Ok(Reprocessed {
+        materials,
+        unprocessed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::tests::INV_TYPES;
+    use crate::tests::item_with_id;
+
+    const INV_GROUPS: &str = "groupID,categoryID,groupName,iconID,useBasePrice,anchored,anchorable,fittableNonSingleton,published
+462,25,Veldspar,None,1,0,0,0,1
+38,7,Shield Extender,None,0,0,0,0,1
+";
+    const INV_TYPE_MATERIALS: &str = "typeID,materialTypeID,quantity
+1230,34,400
+3841,34,2580
+3841,35,540
+";
+
+    #[test]
+    fn yields() {
+        let mut db = TypeDatabase::from_fuzzwork_csv(INV_TYPES.as_bytes()).unwrap();
+        db.load_fuzzwork_groups_csv(INV_GROUPS.as_bytes()).unwrap();
+        db.load_fuzzwork_type_materials_csv(INV_TYPE_MATERIALS.as_bytes())
+            .unwrap();
+        let items = vec![
+            item_with_id("Veldspar", 1230, 250),
+            item_with_id("Large Shield Extender II", 3841, 2),
+            item_with_id("Tritanium", 34, 10),
+        ];
+        let yields = ReprocessingYields {
+            ore: 0.9,
+            modules: 0.5,
+            scrapmetal: 0.55,
+        };
+
+        let reprocessed = reprocess(&db, &items, yields).unwrap();
+        // 2 portions of Veldspar at 90%, 2 extenders at 50%
+        assert_eq!(
+            reprocessed.materials,
+            vec![
+                item_with_id("Tritanium", 34, 720 + 2580),
+                item_with_id("Pyerite", 35, 540)
+            ]
+        );
+        assert_eq!(
+            reprocessed.unprocessed,
+            vec![
+                item_with_id("Veldspar", 1230, 50),
+                item_with_id("Tritanium", 34, 10)
+            ]
+        );
+
+        let too_few = reprocess(&db, &[item_with_id("Veldspar", 1230, 99)], yields).unwrap();
+        assert!(too_few.materials.is_empty());
+        assert_eq!(
+            too_few.unprocessed,
+            vec![item_with_id("Veldspar", 1230, 99)]
+        );
+
+        assert!(reprocess(&db, &items, ReprocessingYields::uniform(1.5)).is_err());
+    }
+}
@@ -0,0 +1,322 @@
+// CPU, powergrid and the other fitting resources a fit uses
+use serde::{Deserialize, Serialize};
+
+use crate::fitting::{resolve_fit, section_for_type, ResolvedFit};
+use crate::{Fit, Hardpoint, ModuleState, Rack, TypeDatabase};
+
+// dgmAttributeTypes IDs
+const ATTRIBUTE_POWER_OUTPUT: u64 = 11;
+const ATTRIBUTE_POWER: u64 = 30;
+const ATTRIBUTE_CPU_OUTPUT: u64 = 48;
+const ATTRIBUTE_CPU: u64 = 50;
+const ATTRIBUTE_RECHARGE_RATE: u64 = 55;
+const ATTRIBUTE_DRONE_CAPACITY: u64 = 283;
+const ATTRIBUTE_CAPACITOR_CAPACITY: u64 = 482;
+const ATTRIBUTE_UPGRADE_CAPACITY: u64 = 1132;
+const ATTRIBUTE_UPGRADE_COST: u64 = 1153;
+const ATTRIBUTE_DRONE_BANDWIDTH: u64 = 1271;
+const ATTRIBUTE_DRONE_BANDWIDTH_USED: u64 = 1272;
+
+// The skills that change fitting resources, from 0 to 5. CPU Management and
+// Power Grid Management add 5% a level to the hull, Weapon Upgrades takes 5%
+// a level off the CPU of turrets and launchers and Advanced Weapon Upgrades
+// 2% a level off their powergrid. Drones is how many drones can be launched.
+// Capacitor Management adds 5% a level to capacitor capacity and Capacitor
+// Systems Operation takes 5% a level off its recharge time.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct FittingSkills {
+    pub cpu_management: u8,
+    pub power_grid_management: u8,
+    pub weapon_upgrades: u8,
+    pub advanced_weapon_upgrades: u8,
+    pub drones: u8,
+    pub capacitor_management: u8,
+    pub capacitor_systems_operation: u8,
+}
+impl FittingSkills {
+    pub fn uniform(level: u8) -> FittingSkills {
+        FittingSkills {
+            cpu_management: level,
+            power_grid_management: level,
+            weapon_upgrades: level,
+            advanced_weapon_upgrades: level,
+            drones: level,
+            capacitor_management: level,
+            capacitor_systems_operation: level,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (name, level) in [
+            ("CPU Management", self.cpu_management),
+            ("Power Grid Management", self.power_grid_management),
+            ("Weapon Upgrades", self.weapon_upgrades),
+            ("Advanced Weapon Upgrades", self.advanced_weapon_upgrades),
+            ("Drones", self.drones),
+            ("Capacitor Management", self.capacitor_management),
+            (
+                "Capacitor Systems Operation",
+                self.capacitor_systems_operation,
+            ),
+        ] {
+            if level > 5 {
+                return Err(format!("{} must be between 0 and 5, got {}", name, level));
+            }
+        }
+        Ok(())
+    }
+}
+// All skills at V
+impl Default for FittingSkills {
+    fn default() -> FittingSkills {
+        FittingSkills::uniform(5)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct Resource {
+    pub used: f64,
+    pub available: f64,
+}
+impl Resource {
+    pub fn exceeded(&self) -> bool {
+        self.used > self.available
+    }
+}
+impl std::fmt::Display for Resource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}/{}", self.used, self.available)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FitResources {
+    pub cpu: Resource,
+    pub powergrid: Resource,
+    pub calibration: Resource,
+    // Only the drones that can be launched at once use bandwidth
+    pub drone_bandwidth: Resource,
+    pub drone_bay: Resource,
+    // In GJ
+    pub capacitor_capacity: f64,
+    // Seconds to recharge from empty
+    pub capacitor_recharge: f64,
+}
+impl FitResources {
+    // The resources the fit needs more of than the hull has, e.g. to catch
+    // fits that can't be fitted at all
+    pub fn exceeded(&self) -> Vec<&'static str> {
+        [
+            ("CPU", self.cpu),
+            ("Powergrid", self.powergrid),
+            ("Calibration", self.calibration),
+            ("Drone bandwidth", self.drone_bandwidth),
+            ("Drone bay", self.drone_bay),
+        ]
+        .into_iter()
+        .filter(|(_, resource)| resource.exceeded())
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+impl std::fmt::Display for FitResources {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "CPU: {} tf", self.cpu)?;
+        writeln!(f, "Powergrid: {} MW", self.powergrid)?;
+        writeln!(f, "Calibration: {}", self.calibration)?;
+        writeln!(f, "Drone bandwidth: {} Mbit/s", self.drone_bandwidth)?;
+        writeln!(f, "Drone bay: {} m3", self.drone_bay)?;
+        write!(
+            f,
+            "Capacitor: {} GJ, {} s recharge",
+            self.capacitor_capacity, self.capacitor_recharge
+        )
+    }
+}
+
+// Adds up the fitting resources a fit uses against what its hull has, with
+// the given skills, and the hull's capacitor. Offline modules don't use CPU
+// or powergrid. Only skills are applied: hull bonuses, and modules and rigs
+// that add CPU, powergrid or capacitor, are not. Drones are launched in the
+// order they're in the fit.
+pub fn fit_resources(
+    db: &TypeDatabase,
+    fit: &Fit,
+    skills: FittingSkills,
+) -> Result<FitResources, String> {
+    skills.validate()?;
+    let ResolvedFit { hull_id, type_ids } = resolve_fit(db, fit)?;
+    let hull_attribute = |attribute_id| db.attribute_value(hull_id, attribute_id);
+    let cpu_output = match hull_attribute(ATTRIBUTE_CPU_OUTPUT) {
+        Some(cpu_output) => cpu_output,
+        None => {
+            return Err(format!(
+                "{} has no CPU output; is it a ship, and are dgmTypeAttributes loaded?",
+                fit.header.hull
+            ))
+        }
+    };
+    let bonus = |level: u8, per_level: f64| 1.0 + level as f64 * per_level;
+
+    let mut cpu = 0.0;
+    let mut powergrid = 0.0;
+    let mut calibration = 0.0;
+    let mut drone_bandwidth = 0.0;
+    let mut drone_bay = 0.0;
+    let mut launchable = skills.drones as u64;
+    for (item, (type_id, _)) in fit.items.iter().zip(type_ids) {
+        let quantity = item.quantity as f64;
+        let attribute = |attribute_id| db.attribute_value(type_id, attribute_id).unwrap_or(0.0);
+        match Rack::for_type(db, type_id) {
+            Some(Rack::Rig) => calibration += attribute(ATTRIBUTE_UPGRADE_COST) * quantity,
+            Some(_) if item.state == Some(ModuleState::Offline) => (),
+            Some(_) => {
+                let (cpu_reduction, powergrid_reduction) = match Hardpoint::for_type(db, type_id) {
+                    Some(_) => (
+                        bonus(skills.weapon_upgrades, -0.05),
+                        bonus(skills.advanced_weapon_upgrades, -0.02),
+                    ),
+                    None => (1.0, 1.0),
+                };
+                cpu += attribute(ATTRIBUTE_CPU) * cpu_reduction * quantity;
+                powergrid += attribute(ATTRIBUTE_POWER) * powergrid_reduction * quantity;
+            }
+            None if section_for_type(db, type_id) == "Drone Bay" => {
+                drone_bay += db.volume(type_id) * quantity;
+                let launched = item.quantity.min(launchable);
+                launchable -= launched;
+                drone_bandwidth += attribute(ATTRIBUTE_DRONE_BANDWIDTH_USED) * launched as f64;
+            }
+            None => (),
+        }
+    }
+
+    let available = |attribute_id| hull_attribute(attribute_id).unwrap_or(0.0);
+    Ok(FitResources {
+        cpu: Resource {
+            used: cpu,
+            available: cpu_output * bonus(skills.cpu_management, 0.05),
+        },
+        powergrid: Resource {
+            used: powergrid,
+            available: available(ATTRIBUTE_POWER_OUTPUT)
+                * bonus(skills.power_grid_management, 0.05),
+        },
+        calibration: Resource {
+            used: calibration,
+            available: available(ATTRIBUTE_UPGRADE_CAPACITY),
+        },
+        drone_bandwidth: Resource {
+            used: drone_bandwidth,
+            available: available(ATTRIBUTE_DRONE_BANDWIDTH),
+        },
+        drone_bay: Resource {
+            used: drone_bay,
+            available: available(ATTRIBUTE_DRONE_CAPACITY),
+        },
+        capacitor_capacity: available(ATTRIBUTE_CAPACITOR_CAPACITY)
+            * bonus(skills.capacitor_management, 0.05),
+        // rechargeRate is in milliseconds
+        capacitor_recharge: available(ATTRIBUTE_RECHARGE_RATE) / 1000.0
+            * bonus(skills.capacitor_systems_operation, -0.05),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::tests::INV_TYPES;
+    use crate::parse_fit;
+
+    const INV_GROUPS: &str = "groupID,categoryID,groupName,iconID,useBasePrice,anchored,anchorable,fittableNonSingleton,published
+900,6,Marauder,None,0,0,0,0,1
+100,18,Combat Drone,None,0,0,0,0,1
+";
+    const DGM_TYPE_ATTRIBUTES: &str = "typeID,attributeID,valueInt,valueFloat
+28659,48,675,None
+28659,11,14000,None
+28659,1132,400,None
+28659,1271,25,None
+28659,283,75,None
+28659,482,5500,None
+28659,55,1120000,None
+3540,50,55,None
+3540,30,2875,None
+3057,50,55,None
+3057,30,2625,None
+26082,1153,150,None
+2488,1272,5,None
+";
+    const DGM_TYPE_EFFECTS: &str = "typeID,effectID,isDefault
+3540,11,0
+3057,12,0
+3057,42,0
+26082,2663,0
+";
+
+    #[test]
+    fn resources() {
+        let mut db = TypeDatabase::from_fuzzwork_csv(INV_TYPES.as_bytes()).unwrap();
+        db.load_fuzzwork_groups_csv(INV_GROUPS.as_bytes()).unwrap();
+        db.load_fuzzwork_type_attributes_csv(DGM_TYPE_ATTRIBUTES.as_bytes())
+            .unwrap();
+        db.load_fuzzwork_type_effects_csv(DGM_TYPE_EFFECTS.as_bytes())
+            .unwrap();
+        let fit = parse_fit(
+            "[Paladin]
+Large Armor Repairer II
+Large Armor Repairer II /OFFLINE
+
+[Empty Med slot]
+
+Mega Pulse Laser II
+Mega Pulse Laser II
+Mega Pulse Laser II
+
+Large Trimark Armor Pump I
+
+Hobgoblin II x6",
+        )
+        .unwrap();
+
+        let resources = fit_resources(&db, &fit, FittingSkills::default()).unwrap();
+        assert_eq!(
+            resources.cpu,
+            Resource {
+                used: 55.0 + 3.0 * 55.0 * 0.75,
+                available: 843.75,
+            }
+        );
+        assert!((resources.powergrid.used - (2875.0 + 3.0 * 2625.0 * 0.9)).abs() < 1e-6);
+        assert_eq!(resources.powergrid.available, 17500.0);
+        assert_eq!(resources.calibration.to_string(), "150/400");
+        // Only five of the six drones can be launched
+        assert_eq!(resources.drone_bandwidth.to_string(), "25/25");
+        assert_eq!(resources.drone_bay.to_string(), "30/75");
+        assert!(resources.exceeded().is_empty());
+        assert_eq!(resources.capacitor_capacity, 6875.0);
+        assert_eq!(resources.capacitor_recharge, 840.0);
+        assert!(resources
+            .to_string()
+            .ends_with("Capacitor: 6875 GJ, 840 s recharge"));
+
+        let untrained = fit_resources(&db, &fit, FittingSkills::uniform(0)).unwrap();
+        assert_eq!(untrained.cpu.to_string(), "220/675");
+        assert_eq!(untrained.drone_bandwidth.used, 0.0);
+        assert_eq!(untrained.capacitor_capacity, 5500.0);
+        assert_eq!(untrained.capacitor_recharge, 1120.0);
+
+        let mut too_many = fit.clone();
+        too_many.items.last_mut().unwrap().quantity = 20;
+        let resources = fit_resources(&db, &too_many, FittingSkills::default()).unwrap();
+        assert_eq!(resources.exceeded(), vec!["Drone bay"]);
+
+        assert!(fit_resources(&db, &fit, FittingSkills::uniform(6)).is_err());
+        assert!(fit_resources(
+            &db,
+            &parse_fit("[Hobgoblin II]").unwrap(),
+            FittingSkills::default()
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,149 @@
+// The skills needed to use a list of items
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::{ItemWithId, TypeDatabase};
+
+// dgmAttributeTypes IDs of requiredSkill1 to requiredSkill6, each with its
+// requiredSkillNLevel
+const ATTRIBUTE_REQUIRED_SKILLS: [(u64, u64); 6] = [
+    (182, 277),
+    (183, 278),
+    (184, 279),
+    (1285, 1286),
+    (1289, 1287),
+    (1290, 1288),
+];
+const ATTRIBUTE_SKILL_RANK: u64 = 275;
+
+// Skill points to train a rank 1 skill from nothing to each level
+const SKILL_POINTS: [u64; 6] = [0, 250, 1415, 8000, 45255, 256000];
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SkillRequirement {
+    pub skill_type_id: u64,
+    pub skill_name: String,
+    pub level: u8,
+    // The skill's training time multiplier, when dgmTypeAttributes has it
+    pub rank: Option<u64>,
+}
+impl SkillRequirement {
+    // From nothing to the required level
+    pub fn skill_points(&self) -> Option<u64> {
+        Some(SKILL_POINTS[self.level.min(5) as usize] * self.rank?)
+    }
+
+    // How long skill_points takes at a training speed, which comes from the
+    // character's attributes, implants and boosters
+    pub fn training_time(&self, skill_points_per_hour: f64) -> Option<Duration> {
+        let hours = self.skill_points()? as f64 / skill_points_per_hour;
+        Duration::try_from_secs_f64(hours * 3600.0).ok()
+    }
+}
+impl std::fmt::Display for SkillRequirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {}", self.skill_name, self.level)
+    }
+}
+
+// Every skill the items need, including the skills those skills need, at the
+// highest level anything needs it. Sorted by skill name.
+pub fn required_skills(db: &TypeDatabase, items: &[ItemWithId]) -> Vec<SkillRequirement> {
+    fn visit(db: &TypeDatabase, type_id: u64, levels: &mut HashMap<u64, u8>, seen: &mut Vec<u64>) {
+        if seen.contains(&type_id) {
+            return;
+        }
+        seen.push(type_id);
+        for (skill_attribute, level_attribute) in ATTRIBUTE_REQUIRED_SKILLS {
+            let skill_type_id = match db.attribute_value(type_id, skill_attribute) {
+                Some(skill_type_id) => skill_type_id as u64,
+                None => continue,
+            };
+            let level = db
+                .attribute_value(type_id, level_attribute)
+                .map_or(1, |level| level as u8);
+            let required = levels.entry(skill_type_id).or_default();
+            *required = (*required).max(level);
+            visit(db, skill_type_id, levels, seen);
+        }
+    }
+
+    let mut levels = HashMap::new();
+    let mut seen = Vec::new();
+    for item in items {
+        visit(db, item.type_id, &mut levels, &mut seen);
+    }
+
+    let mut requirements: Vec<SkillRequirement> = levels
+        .into_iter()
+        .map(|(skill_type_id, level)| SkillRequirement {
+            skill_type_id,
+            skill_name: db.type_name(skill_type_id).unwrap_or("Unknown").to_string(),
+            level,
+            rank: db
+                .attribute_value(skill_type_id, ATTRIBUTE_SKILL_RANK)
+                .map(|rank| rank as u64),
+        })
+        .collect();
+    requirements.sort_by(|a, b| {
+        a.skill_name
+            .cmp(&b.skill_name)
+            .then(a.skill_type_id.cmp(&b.skill_type_id))
+    });
+    requirements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::tests::INV_TYPES;
+
+    // LSE II needs Shield Upgrades 4, whose prerequisites need Power Grid
+    // Management at 1 and 2
+    const DGM_TYPE_ATTRIBUTES: &str = "typeID,attributeID,valueInt,valueFloat
+3841,182,3425,None
+3841,277,4,None
+3839,182,3425,None
+3839,277,1,None
+3425,182,3413,None
+3425,277,1,None
+3425,183,3416,None
+3425,278,2,None
+3425,275,2,None
+3416,182,3413,None
+3416,277,2,None
+3416,275,1,None
+3413,275,1,None
+";
+
+    #[test]
+    fn requirements() {
+        let mut db = TypeDatabase::from_fuzzwork_csv(INV_TYPES.as_bytes()).unwrap();
+        db.load_fuzzwork_type_attributes_csv(DGM_TYPE_ATTRIBUTES.as_bytes())
+            .unwrap();
+        let items = vec![
+            ItemWithId::from_type_id(&db, 3839, 1),
+            ItemWithId::from_type_id(&db, 3841, 2),
+        ];
+
+        let skills = required_skills(&db, &items);
+        let names: Vec<String> = skills.iter().map(|skill| skill.to_string()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Power Grid Management 2",
+                "Shield Operation 2",
+                "Shield Upgrades 4"
+            ]
+        );
+        assert_eq!(skills[2].rank, Some(2));
+        assert_eq!(skills[2].skill_points(), Some(90510));
+        // 1415 skill points at 1800 an hour
+        assert_eq!(
+            skills[0].training_time(1800.0),
+            Some(Duration::from_secs(2830))
+        );
+        assert!(required_skills(&db, &[ItemWithId::from_type_id(&db, 3413, 1)]).is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+// The binary snapshot format behind TypeDatabase::write_snapshot and
+// TypeDatabase::from_snapshot. It's a magic number and a format version
+// followed by the tables in a fixed order, with everything little-endian and
+// strings length-prefixed. Snapshots are only read by the version that wrote
+// them; bump VERSION whenever the layout changes.
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 8] = b"EVEIPTDB";
+const VERSION: u32 = 1;
+
+pub(crate) struct SnapshotWriter<W: Write> {
+    writer: W,
+}
+impl<W: Write> SnapshotWriter<W> {
+    pub(crate) fn new(mut writer: W) -> Result<SnapshotWriter<W>, String> {
+        writer.write_all(MAGIC).map_err(write_error)?;
+        writer
+            .write_all(&VERSION.to_le_bytes())
+            .map_err(write_error)?;
+        Ok(SnapshotWriter { writer })
+    }
+    pub(crate) fn u64(&mut self, v: u64) -> Result<(), String> {
+        self.writer.write_all(&v.to_le_bytes()).map_err(write_error)
+    }
+    pub(crate) fn f64(&mut self, v: f64) -> Result<(), String> {
+        self.writer.write_all(&v.to_le_bytes()).map_err(write_error)
+    }
+    pub(crate) fn bool(&mut self, v: bool) -> Result<(), String> {
+        self.writer.write_all(&[v as u8]).map_err(write_error)
+    }
+    pub(crate) fn optional_u64(&mut self, v: Option<u64>) -> Result<(), String> {
+        self.bool(v.is_some())?;
+        self.u64(v.unwrap_or(0))
+    }
+    pub(crate) fn optional_f64(&mut self, v: Option<f64>) -> Result<(), String> {
+        self.bool(v.is_some())?;
+        self.f64(v.unwrap_or(0.0))
+    }
+    pub(crate) fn optional_string(&mut self, s: Option<&str>) -> Result<(), String> {
+        self.bool(s.is_some())?;
+        self.string(s.unwrap_or(""))
+    }
+    pub(crate) fn string(&mut self, s: &str) -> Result<(), String> {
+        self.u64(s.len() as u64)?;
+        self.writer.write_all(s.as_bytes()).map_err(write_error)
+    }
+    pub(crate) fn finish(mut self) -> Result<(), String> {
+        self.writer.flush().map_err(write_error)
+    }
+}
+
+pub(crate) struct SnapshotReader<R: Read> {
+    reader: R,
+}
+impl<R: Read> SnapshotReader<R> {
+    pub(crate) fn new(mut reader: R) -> Result<SnapshotReader<R>, String> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic).map_err(read_error)?;
+        if &magic != MAGIC {
+            return Err("not a type database snapshot".to_string());
+        }
+        let mut r = SnapshotReader { reader };
+        let mut version = [0; 4];
+        r.reader.read_exact(&mut version).map_err(read_error)?;
+        let version = u32::from_le_bytes(version);
+        if version != VERSION {
+            return Err(format!(
+                "snapshot is format version {}, expected {}; write a new one",
+                version, VERSION
+            ));
+        }
+        Ok(r)
+    }
+    pub(crate) fn u64(&mut self) -> Result<u64, String> {
+        let mut bytes = [0; 8];
+        self.reader.read_exact(&mut bytes).map_err(read_error)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+    pub(crate) fn f64(&mut self) -> Result<f64, String> {
+        let mut bytes = [0; 8];
+        self.reader.read_exact(&mut bytes).map_err(read_error)?;
+        Ok(f64::from_le_bytes(bytes))
+    }
+    pub(crate) fn bool(&mut self) -> Result<bool, String> {
+        let mut byte = [0; 1];
+        self.reader.read_exact(&mut byte).map_err(read_error)?;
+        match byte[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(format!("invalid bool {} in snapshot", b)),
+        }
+    }
+    pub(crate) fn optional_u64(&mut self) -> Result<Option<u64>, String> {
+        let present = self.bool()?;
+        let v = self.u64()?;
+        Ok(present.then_some(v))
+    }
+    pub(crate) fn optional_f64(&mut self) -> Result<Option<f64>, String> {
+        let present = self.bool()?;
+        let v = self.f64()?;
+        Ok(present.then_some(v))
+    }
+    pub(crate) fn optional_string(&mut self) -> Result<Option<String>, String> {
+        let present = self.bool()?;
+        let s = self.string()?;
+        Ok(present.then_some(s))
+    }
+    pub(crate) fn string(&mut self) -> Result<String, String> {
+        let len = self.u64()?;
+        let mut bytes = Vec::new();
+        (&mut self.reader)
+            .take(len)
+            .read_to_end(&mut bytes)
+            .map_err(read_error)?;
+        if bytes.len() as u64 != len {
+            return Err("snapshot ends in the middle of a string".to_string());
+        }
+        String::from_utf8(bytes).map_err(|e| format!("invalid string in snapshot: {}", e))
+    }
+}
+
+fn write_error(e: std::io::Error) -> String {
+    format!("writing snapshot: {}", e)
+}
+
+fn read_error(e: std::io::Error) -> String {
+    format!("reading snapshot: {}", e)
+}
@@ -0,0 +1,124 @@
+// How much space a list of items takes up, for hauling
+use serde::{Deserialize, Serialize};
+
+use crate::{ItemWithId, Quantity, TypeDatabase};
+
+// Ships and containers take up far less room packaged than assembled. Only
+// packaged ships can go in a cargo hold or a courier contract.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Packaging {
+    #[default]
+    Packaged,
+    Assembled,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct VolumeLine {
+    pub type_name: String,
+    pub type_id: u64,
+    pub quantity: Quantity,
+    // m3 for one of the item
+    pub unit_volume: f64,
+    // m3 for the whole line
+    pub volume: f64,
+}
+impl std::fmt::Display for VolumeLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} x{}: {} m3",
+            self.type_name, self.quantity, self.volume
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct VolumeReport {
+    // One per input item, in the same order
+    pub lines: Vec<VolumeLine>,
+    pub total: f64,
+}
+impl std::fmt::Display for VolumeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for line in self.lines.iter() {
+            writeln!(f, "{}", line)?;
+        }
+        write!(f, "Total: {} m3", self.total)
+    }
+}
+
+pub fn calculate_volume(
+    db: &TypeDatabase,
+    items: &[ItemWithId],
+    packaging: Packaging,
+) -> Result<VolumeReport, String> {
+    let mut lines = Vec::new();
+    let mut errors = Vec::new();
+    for item in items {
+        let unit_volume = match packaging {
+            Packaging::Packaged => db.packaged_volume(item.type_id),
+            Packaging::Assembled => db.type_info(item.type_id).map(|info| info.volume),
+        };
+        let unit_volume = match unit_volume {
+            Some(unit_volume) => unit_volume,
+            None => {
+                errors.push(format!(
+                    "no volume for {} ({})",
+                    item.type_name, item.type_id
+                ));
+                continue;
+            }
+        };
+        lines.push(VolumeLine {
+            type_name: item.type_name.clone(),
+            type_id: item.type_id,
+            quantity: item.quantity,
+            unit_volume,
+            volume: unit_volume * item.quantity as f64,
+        });
+    }
+    if !errors.is_empty() {
+        return Err(format!("{:?}", errors));
+    }
+
+    let total = lines.iter().map(|line| line.volume).sum();
+    Ok(VolumeReport { lines, total })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::tests::{INV_TYPES, INV_VOLUMES};
+    use crate::tests::item_with_id;
+
+    #[test]
+    fn volumes() {
+        let mut db = TypeDatabase::from_fuzzwork_csv(INV_TYPES.as_bytes()).unwrap();
+        db.load_fuzzwork_volumes_csv(INV_VOLUMES.as_bytes())
+            .unwrap();
+        let items = vec![
+            item_with_id("Drake", 24698, 2),
+            item_with_id("Large Shield Extender II", 3841, 5),
+        ];
+
+        let packaged = calculate_volume(&db, &items, Packaging::Packaged).unwrap();
+        assert_eq!(packaged.lines[0].unit_volume, 15000.0);
+        assert_eq!(packaged.lines[0].volume, 30000.0);
+        assert_eq!(packaged.lines[1].volume, 50.0);
+        assert_eq!(packaged.total, 30050.0);
+        assert_eq!(
+            packaged.to_string(),
+            "Drake x2: 30000 m3\nLarge Shield Extender II x5: 50 m3\nTotal: 30050 m3"
+        );
+
+        let assembled = calculate_volume(&db, &items, Packaging::Assembled).unwrap();
+        assert_eq!(assembled.total, 504050.0);
+
+        assert!(calculate_volume(
+            &db,
+            &[item_with_id("Mystery Box", 1, 1)],
+            Packaging::default()
+        )
+        .is_err());
+    }
+}